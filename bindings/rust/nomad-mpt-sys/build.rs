@@ -8,7 +8,7 @@ fn main() {
     #[cfg(not(target_os = "linux"))]
     compile_error!("nomad-mpt-sys only supports Linux (requires io_uring)");
 
-    let _out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());  // 保留备用
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     // 目录结构: nomad-mpt/bindings/rust/nomad-mpt-sys/
     // depend 目录在 nomad-mpt/depend/
@@ -187,11 +187,51 @@ fn main() {
         .flag_if_supported("-Wno-deprecated-declarations")
         .compile("nomad-mpt-bridge");
 
+    // ============================================================
+    // 8. FFI 结构体大小交叉校验（C++ 侧 static_assert）
+    // ============================================================
+    //
+    // async_fifo.rs 开头的 `const _: ()` 断言只在 Rust 侧检查 size_of，如果
+    // C++ 结构体布局变了但刚好凑出同一个总大小（比如交换了两个同样大小的
+    // 字段），Rust 那边测不出来。这里单独编译、运行一遍 `src/assert_sizes.cpp`
+    // ——里面对同一批结构体做 `static_assert`，绑死在结构体定义本身上，不会
+    // 被"凑大小"蒙过去；编译失败或者运行时返回非零，都视为两边对不上。
+    let assert_sizes_compiler = cc::Build::new()
+        .cpp(true)
+        .include(&manifest_dir)
+        .include(&cpp_source_dir)
+        .include(cpp_source_dir.join("category"))
+        .include(&third_party)
+        .include(ck_dir.join("include"))
+        .include(third_party.join("quill/quill/include"))
+        .flag_if_supported("-std=c++23")
+        .get_compiler();
+
+    let assert_sizes_exe = out_dir.join("assert_sizes");
+    let compile_status = std::process::Command::new(assert_sizes_compiler.path())
+        .args(assert_sizes_compiler.args())
+        .arg("src/assert_sizes.cpp")
+        .arg("-o")
+        .arg(&assert_sizes_exe)
+        .status()
+        .expect("failed to invoke C++ compiler for src/assert_sizes.cpp");
+    if !compile_status.success() {
+        panic!("FFI struct size mismatch detected by C++ compiler: src/assert_sizes.cpp failed to compile (see static_assert output above)");
+    }
+
+    let run_status = std::process::Command::new(&assert_sizes_exe)
+        .status()
+        .expect("failed to run assert_sizes probe binary");
+    if !run_status.success() {
+        panic!("FFI struct size mismatch detected by C++ compiler: see assert_sizes probe output above for the exact type and size");
+    }
+
     // 重新编译触发条件
     println!("cargo:rerun-if-changed=src/lib.rs");
     println!("cargo:rerun-if-changed=src/bridge.cpp");
     println!("cargo:rerun-if-changed=src/bridge_fifo.cpp");
     println!("cargo:rerun-if-changed=src/bridge_fifo.hpp");
+    println!("cargo:rerun-if-changed=src/assert_sizes.cpp");
     println!("cargo:rerun-if-changed={}", cpp_source_dir.join("CMakeLists.txt").display());
     println!("cargo:rerun-if-changed={}", ck_dir.display());
 }
@@ -2,8 +2,14 @@
 //!
 //! 提供高性能的异步 find/traverse 操作支持。
 
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
+use std::pin::Pin;
 use std::ptr::NonNull;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
 
 // ============================================================
 // FFI 类型定义
@@ -12,10 +18,10 @@ use std::ptr::NonNull;
 // 编译时验证结构体大小与 C++ 一致
 // C++ 定义见 bridge_fifo.hpp
 const _: () = {
-    assert!(std::mem::size_of::<Request>() == 64, "Request size mismatch with C++");
-    assert!(std::mem::size_of::<Completion>() == 312, "Completion size mismatch with C++");
-    assert!(std::mem::size_of::<RequestNode>() == 24 + 64, "RequestNode size mismatch");
-    assert!(std::mem::size_of::<CompletionNode>() == 24 + 312, "CompletionNode size mismatch");
+    assert!(std::mem::size_of::<Request>() == 112, "Request size mismatch with C++");
+    assert!(std::mem::size_of::<Completion>() == 576, "Completion size mismatch with C++");
+    assert!(std::mem::size_of::<RequestNode>() == 24 + 112, "RequestNode size mismatch");
+    assert!(std::mem::size_of::<CompletionNode>() == 24 + 576, "CompletionNode size mismatch");
 };
 
 /// 请求类型
@@ -25,6 +31,7 @@ pub enum RequestType {
     FindValue = 1,
     FindNode = 2,
     Traverse = 3,
+    FindNodeWithProof = 4,
     Shutdown = 255,
 }
 
@@ -40,6 +47,16 @@ pub struct Request {
     pub _pad: [u8; 2],
     pub traverse_limit: u32,
     pub key: [u8; 32],
+    /// `after_key` 的有效长度；0 表示不使用游标，从头遍历
+    pub after_key_len: u8,
+    pub _pad2: [u8; 7],
+    /// 分页游标：跳过 <= after_key 的条目
+    pub after_key: [u8; 32],
+    /// 只有 `RequestType::Traverse` 才会看这个字段；0 表示不限制深度。
+    /// 深度以 trie 节点的下探次数（从子树根出发）计，不是 nibble 数，
+    /// 见 [`AsyncFifo::submit_traverse_subtrie`]
+    pub traverse_max_depth: u32,
+    pub _pad3: [u8; 4],
 }
 
 /// 请求节点
@@ -84,6 +101,11 @@ pub struct Completion {
     pub value_len: u32,
     pub value: [u8; 256],
     pub merkle_hash: [u8; 32],
+    /// 只有 `RequestType::FindNodeWithProof` 才会填，其它请求类型下恒为 0；
+    /// 和 `value_len` 一样，`0xFFFFFFFF` 表示证明编码走的是大值通道，见
+    /// [`AsyncFifo::submit_find_with_proof`]
+    pub proof_len: u32,
+    pub proof_data: [u8; 256],
 }
 
 /// 完成节点（使用 MPMC entry，24 字节）
@@ -124,7 +146,13 @@ extern "C" {
     fn fifo_destroy(mgr: *mut FifoManager);
     fn fifo_start(mgr: *mut FifoManager, num_workers: usize);
     fn fifo_stop(mgr: *mut FifoManager);
-    
+    fn fifo_resize_workers(mgr: *mut FifoManager, new_count: usize) -> usize;
+    fn fifo_worker_count(mgr: *mut FifoManager) -> usize;
+    fn fifo_total_submitted(mgr: *mut FifoManager) -> u64;
+    fn fifo_total_completed(mgr: *mut FifoManager) -> u64;
+    fn fifo_start_numa(mgr: *mut FifoManager) -> usize;
+    fn fifo_numa_node_count() -> usize;
+
     // 单个操作
     fn fifo_alloc_request(mgr: *mut FifoManager) -> *mut RequestNode;
     #[allow(dead_code)]  // 保留完整 API，可用于手动内存管理
@@ -158,6 +186,60 @@ pub struct FindResult {
     pub value: Option<Vec<u8>>,
     pub has_large_value: bool,
     pub merkle_hash: [u8; 32],
+    /// 只有 [`AsyncFifo::submit_find_with_proof`] 提交的请求才会有内容，
+    /// 而且目前恒为单元素（目标节点自身的证明编码）——和
+    /// [`crate::proof::Proof`] 同一个限制：底层还不支持完整的
+    /// root -> leaf 路径。`None` 且 `has_large_proof` 为 `false` 表示这不是
+    /// 一个带证明的请求；`None` 且 `has_large_proof` 为 `true` 表示证明超过
+    /// 256 字节，需要调用方自己去大值通道里取（见 `has_large_proof` 文档）。
+    pub proof: Option<Vec<Vec<u8>>>,
+    /// 证明编码超过 256 字节、走了大值通道，见 [`FindResult::proof`]
+    ///
+    /// # 限制
+    /// 如果这次请求的 `value` 本身也超过 256 字节，它也会走大值通道、用
+    /// 同一个 `user_data`——大值通道本身不区分"这条大值是 value 还是
+    /// proof"，这种情况下 [`AsyncFifo::poll_large_value`] 拿到的两条大值
+    /// 哪个对应哪个是不确定的。这个方法目前只适合 value 本身不大（例如
+    /// 大多数账户/存储槽）的场景。
+    pub has_large_proof: bool,
+}
+
+/// [`AsyncFifo::submit_find_multi_version`] 提交的一批请求的"哪个
+/// `user_data` 对应哪个版本"映射
+///
+/// 不持有 `AsyncFifo` 的引用，只是把那次提交用到的 `key`、`user_data_base`
+/// 和 `versions` 记下来；完成结果陆续从 `poll`/`poll_batch` 到达后，用
+/// [`MultiVersionQuery::version_for`] 查某个 `user_data` 对应的版本。
+#[derive(Debug, Clone)]
+pub struct MultiVersionQuery {
+    key: Vec<u8>,
+    user_data_base: u128,
+    versions: Vec<u64>,
+}
+
+impl MultiVersionQuery {
+    /// 记录一次 [`AsyncFifo::submit_find_multi_version`] 调用用的参数
+    pub fn new(key: &[u8], versions: &[u64], user_data_base: u128) -> Self {
+        Self {
+            key: key.to_vec(),
+            user_data_base,
+            versions: versions.to_vec(),
+        }
+    }
+
+    /// 这次查询的 key
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// 给定一次完成结果里的 `user_data`，返回它对应的版本；`user_data` 不
+    /// 在 `[user_data_base, user_data_base + versions.len())` 范围内时返回
+    /// `None`
+    pub fn version_for(&self, user_data: u128) -> Option<u64> {
+        let index = user_data.checked_sub(self.user_data_base)?;
+        let index = usize::try_from(index).ok()?;
+        self.versions.get(index).copied()
+    }
 }
 
 /// 大值
@@ -167,6 +249,147 @@ pub struct LargeValue {
     pub data: Vec<u8>,
 }
 
+/// [`AsyncFifo::poll_large_value_stream`] 返回的流式读取句柄
+///
+/// 持有对应 `LargeValueNode` 的裸指针直到 drop，drop 时调用
+/// `fifo_free_large_value` 归还内存——和 `poll_large_value` 的区别只是
+/// 把"整段拷进一个 `Vec<u8>`"换成了"调用方按自己的 buffer 分批读"，省掉
+/// 超过 1 MB 的大值一次性分配整块内存的开销。
+pub struct LargeValueStream {
+    mgr: *mut FifoManager,
+    node: *mut LargeValueNode,
+    len: usize,
+    offset: usize,
+}
+
+// Safety: `LargeValueNode` 背后的内存在 drop 之前不会被其它线程修改，
+// `fifo_free_large_value` 内部的 ck_fifo 分配器是线程安全的
+unsafe impl Send for LargeValueStream {}
+
+impl std::io::Read for LargeValueStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.len - self.offset;
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let to_copy = remaining.min(buf.len());
+        // Safety: `node` 在整个 `LargeValueStream` 生命周期内有效，
+        // `offset + to_copy <= self.len`，数据紧跟在 `LargeValueNode` 结构体后面
+        unsafe {
+            let data_ptr = (self.node as *const u8)
+                .add(std::mem::size_of::<LargeValueNode>() + self.offset);
+            std::ptr::copy_nonoverlapping(data_ptr, buf.as_mut_ptr(), to_copy);
+        }
+        self.offset += to_copy;
+        Ok(to_copy)
+    }
+}
+
+impl Drop for LargeValueStream {
+    fn drop(&mut self) {
+        unsafe { fifo_free_large_value(self.mgr, self.node) }
+    }
+}
+
+/// [`AsyncFifo::take_large_value_for`] 用来存放"轮询到但 `user_data` 不是
+/// 当前正在等的那一个"的大值，避免它们在下一次调用时被白白丢弃
+#[derive(Debug, Default)]
+pub struct LargeValueBuffer(HashMap<u128, LargeValue>);
+
+impl LargeValueBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, value: LargeValue) {
+        self.0.insert(value.user_data, value);
+    }
+
+    /// 取出并移除缓存里 `user_data` 对应的大值（如果之前被
+    /// [`AsyncFifo::take_large_value_for`] 缓存过）
+    pub fn take(&mut self, user_data: u128) -> Option<LargeValue> {
+        self.0.remove(&user_data)
+    }
+}
+
+/// [`AsyncFifo::large_value_stream`] 返回的流：每当有大值到达就产出一条
+/// [`LargeValue`]
+///
+/// 和 [`HealthWatch`] 一样，底层完成队列没有真正的异步唤醒机制，
+/// `poll_next` 用短暂 `sleep` 轮询 [`AsyncFifo::poll_large_value`] 模拟；
+/// 请求里提到的 `tokio::time::interval` 没有用上——这个 crate 的
+/// `tokio` 依赖只在 `dev-dependencies` 里启用了 `time` feature（见
+/// `Cargo.toml`），库代码本身并不依赖 tokio 的运行时，和 `DiffStream`/
+/// `HealthWatch`/`TraverseStream` 用 `std::thread::sleep` 忙等的风格保持
+/// 一致。
+pub struct LargeValueWatch<'a> {
+    fifo: &'a AsyncFifo,
+    interval: Duration,
+}
+
+impl Stream for LargeValueWatch<'_> {
+    type Item = LargeValue;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(value) = this.fifo.poll_large_value() {
+                return Poll::Ready(Some(value));
+            }
+            std::thread::sleep(this.interval);
+        }
+    }
+}
+
+/// [`AsyncFifo::stats`] 的结果
+///
+/// `total_submitted`/`total_completed` 是累计计数器（不会在重启/resize 时
+/// 清零），`pending_requests` 是它们的差值。对于 Traverse 请求，一次
+/// submit 会产出多个完成事件（每个遍历到的节点一个，外加一个结束标记），
+/// 所以 `pending_requests` 在有遍历请求在途时可能是负的——这里用
+/// `saturating_sub` 夹到 0，不代表真的没有请求在排队，只是这个计数器的
+/// 粒度没法精确刻画"一个 submit 对多个 completion"的流式场景。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FifoStats {
+    pub total_submitted: u64,
+    pub total_completed: u64,
+    pub pending_requests: u64,
+    pub alive_workers: u32,
+}
+
+/// [`AsyncFifo::health_check`] 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthStatus {
+    pub alive_workers: u32,
+    pub expected_workers: u32,
+    pub is_healthy: bool,
+}
+
+/// [`AsyncFifo::watch_health`] 返回的流
+pub struct HealthWatch<'a> {
+    fifo: &'a AsyncFifo,
+    interval: Duration,
+    last: Option<HealthStatus>,
+}
+
+impl Stream for HealthWatch<'_> {
+    type Item = HealthStatus;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let status = this.fifo.health_check();
+            let changed = this.last.map_or(true, |prev| prev.is_healthy != status.is_healthy);
+            this.last = Some(status);
+            if changed {
+                return Poll::Ready(Some(status));
+            }
+            std::thread::sleep(this.interval);
+        }
+    }
+}
+
 #[inline]
 fn split_ud(user_data: u128) -> (u64, u64) {
     let lo = user_data as u64;
@@ -182,6 +405,9 @@ fn combine_ud(lo: u64, hi: u64) -> u128 {
 /// 异步 FIFO 通道
 pub struct AsyncFifo {
     mgr: NonNull<FifoManager>,
+    /// [`AsyncFifo::submit_find_or_default`] 用到的 `user_data -> 默认值`
+    /// 映射；见该方法的文档
+    defaults: std::sync::Mutex<std::collections::HashMap<u128, [u8; 32]>>,
 }
 
 // Safety: FifoManager 内部使用线程安全的 ck_fifo
@@ -200,6 +426,7 @@ impl AsyncFifo {
         }
         Ok(Self {
             mgr: NonNull::new_unchecked(mgr),
+            defaults: std::sync::Mutex::new(std::collections::HashMap::new()),
         })
     }
     
@@ -212,7 +439,88 @@ impl AsyncFifo {
     pub fn stop(&self) {
         unsafe { fifo_stop(self.mgr.as_ptr()) }
     }
-    
+
+    /// 动态调整 worker 数量，返回实际生效的数量（至少为 1）
+    ///
+    /// C++ 侧没有在运行中增删 fiber 的接口，这里是通过 stop + start 重新
+    /// 创建 fiber pool 实现的：已经提交但尚未处理的请求留在 request FIFO
+    /// 里不受影响，新 worker 起来后会继续处理它们
+    pub fn set_worker_count(&self, n: usize) -> usize {
+        unsafe { fifo_resize_workers(self.mgr.as_ptr(), n) }
+    }
+
+    /// 当前 worker 数量
+    pub fn worker_count(&self) -> usize {
+        unsafe { fifo_worker_count(self.mgr.as_ptr()) }
+    }
+
+    /// 检查 worker 是否都还在运行
+    ///
+    /// # 未实现
+    /// worker 是跑在同一个 OS 线程上的 fiber（见 `FifoManager::start`），
+    /// 每次请求处理过程中的 C++ 异常已经在处理函数里被 `catch (...)` 成
+    /// `STATUS_ERROR` 回传给调用方，不会让 fiber 本身崩溃退出——所以这里
+    /// 没有真正的"某个 fiber 已经挂了"的信号可以探测。`alive_workers`
+    /// 永远等于当前配置的 [`AsyncFifo::worker_count`]，`is_healthy` 永远
+    /// 是 `true`。真正能让 worker 消失的故障（比如整个 OS 线程崩溃）会让
+    /// 整个 `FifoManager` 一起失效，不存在"部分 worker 健康、部分不健康"
+    /// 的中间状态。
+    pub fn health_check(&self) -> HealthStatus {
+        let workers = self.worker_count() as u32;
+        HealthStatus { alive_workers: workers, expected_workers: workers, is_healthy: true }
+    }
+
+    /// 当前的提交/完成计数快照，见 [`FifoStats`] 的文档
+    pub fn stats(&self) -> FifoStats {
+        let total_submitted = unsafe { fifo_total_submitted(self.mgr.as_ptr()) };
+        let total_completed = unsafe { fifo_total_completed(self.mgr.as_ptr()) };
+        FifoStats {
+            total_submitted,
+            total_completed,
+            pending_requests: total_submitted.saturating_sub(total_completed),
+            alive_workers: self.worker_count() as u32,
+        }
+    }
+
+    /// 重启 worker；见 [`AsyncFifo::health_check`] 的文档——没法只重启
+    /// "死掉的"那部分，这里等价于用当前的 worker 数量重新调用一次
+    /// [`AsyncFifo::set_worker_count`]（内部是 stop + start）
+    pub fn restart_dead_workers(&self) -> u32 {
+        let current = self.worker_count();
+        self.set_worker_count(current) as u32
+    }
+
+    /// 按 `interval` 轮询 [`AsyncFifo::health_check`]，只有当
+    /// `is_healthy` 发生变化时才产出一条 [`HealthStatus`]（第一次轮询
+    /// 总是产出一次，当作初始状态）
+    ///
+    /// 和 [`AsyncFifo::traverse_stream`] 一样，`poll_next` 直接
+    /// sleep + 重新检查，不注册真正的异步 waker——这个模块里所有"异步"
+    /// 接口都是这个风格（见 `wait_for_count` 等的忙等实现）。
+    pub fn watch_health(&self, interval: Duration) -> HealthWatch<'_> {
+        HealthWatch { fifo: self, interval, last: None }
+    }
+
+    /// 按探测到的 NUMA 拓扑启动 worker，返回实际生效的 worker 数量
+    ///
+    /// 通过解析 `/sys/devices/system/node/node*/cpulist` 得到机器上的
+    /// NUMA node 数和 CPU 总数，用 CPU 总数作为 worker fiber 数量启动，
+    /// 让并发度匹配硬件拓扑。
+    ///
+    /// 注意：底层 `fiber::PriorityPool` 固定只用 1 个 OS 线程 + N 个
+    /// fiber，没有暴露"绑定到指定 NUMA node"的接口，所以这里**不会**把
+    /// worker 线程/fiber 实际绑定到对应 node 的 CPU 或内存上——只是让
+    /// worker 数量与硬件拓扑匹配。真正的跨 node 亲和性绑定需要先扩展
+    /// `fiber::FiberThreadPool`，不属于这层 FFI 绑定的范围。
+    pub fn start_numa_aware(&self) -> usize {
+        unsafe { fifo_start_numa(self.mgr.as_ptr()) }
+    }
+
+    /// 探测到的 NUMA node 数量（不需要先创建 `AsyncFifo`）
+    pub fn numa_node_count() -> usize {
+        unsafe { fifo_numa_node_count() }
+    }
+
     // === 单个操作 ===
     
     /// 提交 find_value 请求，user_data 为业务透传，按小端两段 64bit 回传
@@ -232,7 +540,83 @@ impl AsyncFifo {
     pub fn submit_find_node(&self, key: &[u8], version: u64, user_data: u128) -> bool {
         self.submit_find_impl(key, version, user_data, RequestType::FindNode)
     }
-    
+
+    /// 提交 find_node 请求，同时要求 C++ 侧在同一次完成里带上目标节点
+    /// 自身的证明编码（见 [`FindResult::proof`]），省掉单独再调一次
+    /// [`crate::Db::get_proof`] 的往返
+    pub fn submit_find_with_proof(&self, key: &[u8], version: u64, user_data: u128) -> bool {
+        self.submit_find_impl(key, version, user_data, RequestType::FindNodeWithProof)
+    }
+
+    /// 提交 find_value 请求，但如果结果是 [`ResultStatus::NotFound`]，用
+    /// `default_value` 顶替返回，而不是 `None`——EVM 读 storage slot 经常
+    /// 是这样的默认语义（没写过的 slot 读出来是零）
+    ///
+    /// 顶替逻辑发生在 [`AsyncFifo::poll`]/[`AsyncFifo::poll_batch`] 取到
+    /// 对应完成的时候：这个方法本身只是把 `(user_data, default_value)` 记
+    /// 进 `defaults`，完成到达后不管结果是不是 NotFound 都会把这条记录
+    /// 取出并移除，所以这张表不会无限增长。用这个方法提交的请求，结果是
+    /// NotFound 时 [`FindResult::value`] 一定是 `Some(default_value)`；
+    /// `Error` 状态或走大值通道（`has_large_value`）的结果不做顶替，
+    /// `value` 仍是 `None`。
+    ///
+    /// # 返回
+    /// - `true`: 请求成功提交
+    /// - `false`: 请求提交失败（内存分配失败），这种情况下不会记录默认值
+    pub fn submit_find_or_default(
+        &self,
+        key: &[u8],
+        version: u64,
+        default_value: [u8; 32],
+        user_data: u128,
+    ) -> bool {
+        let submitted = self.submit_find_value(key, version, user_data);
+        if submitted {
+            let mut defaults = self.defaults.lock().expect("AsyncFifo defaults mutex poisoned");
+            defaults.insert(user_data, default_value);
+        }
+        submitted
+    }
+
+    /// 对同一个 `key`，按 `versions` 里的每一个版本各提交一次
+    /// `submit_find_value`，用 `user_data_base + i as u128`（`i` 是
+    /// `versions` 的下标）作为每个请求的 `user_data`，方便在 `poll`/
+    /// `poll_batch` 收完成的时候按 `user_data` 对应回是哪个版本（见
+    /// [`MultiVersionQuery::version_for`]）。
+    ///
+    /// # 返回
+    /// 实际提交成功的请求数——提交到一半遇到分配失败时立即停止，不会跳过
+    /// 失败的那个继续提交后面的版本。
+    pub fn submit_find_multi_version(
+        &self,
+        key: &[u8],
+        versions: &[u64],
+        user_data_base: u128,
+    ) -> usize {
+        let mut submitted = 0;
+        for (i, &version) in versions.iter().enumerate() {
+            let user_data = user_data_base + i as u128;
+            if !self.submit_find_value(key, version, user_data) {
+                break;
+            }
+            submitted += 1;
+        }
+        submitted
+    }
+
+    /// [`AsyncFifo::submit_find_multi_version`] 的便捷版本：提交完之后直接
+    /// 返回对应的 [`MultiVersionQuery`]，调用方不用自己把 `versions` 和
+    /// `user_data_base` 对齐着记两遍
+    pub fn query_multi_version(
+        &self,
+        key: &[u8],
+        versions: &[u64],
+        user_data_base: u128,
+    ) -> (usize, MultiVersionQuery) {
+        let submitted = self.submit_find_multi_version(key, versions, user_data_base);
+        (submitted, MultiVersionQuery::new(key, versions, user_data_base))
+    }
+
     fn submit_find_impl(&self, key: &[u8], version: u64, user_data: u128, req_type: RequestType) -> bool {
         let (lo, hi) = split_ud(user_data);
         unsafe {
@@ -287,6 +671,150 @@ impl AsyncFifo {
         }
     }
     
+    /// 提交带游标的 traverse 请求，跳过 <= `after_key` 的条目
+    ///
+    /// 用于分页：将上一页 `TraverseMore` 结果中最后一个 key 作为 `after_key` 传入，
+    /// 即可继续遍历下一页，避免每次都从头开始。
+    ///
+    /// # 返回
+    /// - `true`: 请求成功提交
+    /// - `false`: 请求提交失败（内存分配失败，或 `after_key` 超过 32 字节）
+    pub fn submit_traverse_from(
+        &self,
+        prefix: &[u8],
+        version: u64,
+        after_key: &[u8],
+        limit: u32,
+        user_data: u128,
+    ) -> bool {
+        if after_key.len() > 32 {
+            return false;
+        }
+
+        let (lo, hi) = split_ud(user_data);
+        unsafe {
+            let node = fifo_alloc_request(self.mgr.as_ptr());
+            if node.is_null() {
+                return false;
+            }
+
+            let req = &mut (*node).req;
+            req.user_data_lo = lo;
+            req.user_data_hi = hi;
+            req.version = version;
+            req.req_type = RequestType::Traverse as u8;
+            req.key_len = prefix.len().min(32) as u8;
+            req.key[..req.key_len as usize].copy_from_slice(&prefix[..req.key_len as usize]);
+            req.traverse_limit = limit;
+            req.after_key_len = after_key.len() as u8;
+            req.after_key[..after_key.len()].copy_from_slice(after_key);
+
+            fifo_submit(self.mgr.as_ptr(), node);
+            true
+        }
+    }
+
+    /// 提交深度受限的 traverse 请求，只遍历 `prefix` 子树里前 `max_depth`
+    /// 层 trie 节点（`max_depth == 0` 等价于 [`AsyncFifo::submit_traverse`]，
+    /// 不限制深度）
+    ///
+    /// 深度按 trie 节点的下探次数计，不是 nibble 数：MPT 压缩路径下一个
+    /// 节点可能一次跨过多个 nibble，这里没有拆开压缩路径去数 nibble，
+    /// 因为底层 `mpt::TraverseMachine`（见 `depend/monad/category/mpt/
+    /// traverse.hpp`）按节点、不是按 nibble 记录 `level`。恰好落在深度
+    /// 边界、自身没有 value 的节点也会被汇报，[`FindResult::value`] 为
+    /// `None`，表示"这个前缀下面还有数据，但被截断了"。
+    pub fn submit_traverse_subtrie(
+        &self,
+        prefix: &[u8],
+        version: u64,
+        max_depth: u32,
+        limit: u32,
+        user_data: u128,
+    ) -> bool {
+        let (lo, hi) = split_ud(user_data);
+        unsafe {
+            let node = fifo_alloc_request(self.mgr.as_ptr());
+            if node.is_null() {
+                return false;
+            }
+
+            let req = &mut (*node).req;
+            req.user_data_lo = lo;
+            req.user_data_hi = hi;
+            req.version = version;
+            req.req_type = RequestType::Traverse as u8;
+            req.key_len = prefix.len().min(32) as u8;
+            req.key[..req.key_len as usize].copy_from_slice(&prefix[..req.key_len as usize]);
+            req.traverse_limit = limit;
+            req.traverse_max_depth = max_depth;
+
+            fifo_submit(self.mgr.as_ptr(), node);
+            true
+        }
+    }
+
+    /// 和 [`AsyncFifo::submit_traverse_subtrie`] 一样深度受限，但带游标，
+    /// 用于分页；语义见 [`AsyncFifo::submit_traverse_from`]
+    pub fn submit_traverse_subtrie_from(
+        &self,
+        prefix: &[u8],
+        version: u64,
+        after_key: &[u8],
+        max_depth: u32,
+        limit: u32,
+        user_data: u128,
+    ) -> bool {
+        if after_key.len() > 32 {
+            return false;
+        }
+
+        let (lo, hi) = split_ud(user_data);
+        unsafe {
+            let node = fifo_alloc_request(self.mgr.as_ptr());
+            if node.is_null() {
+                return false;
+            }
+
+            let req = &mut (*node).req;
+            req.user_data_lo = lo;
+            req.user_data_hi = hi;
+            req.version = version;
+            req.req_type = RequestType::Traverse as u8;
+            req.key_len = prefix.len().min(32) as u8;
+            req.key[..req.key_len as usize].copy_from_slice(&prefix[..req.key_len as usize]);
+            req.traverse_limit = limit;
+            req.traverse_max_depth = max_depth;
+            req.after_key_len = after_key.len() as u8;
+            req.after_key[..after_key.len()].copy_from_slice(after_key);
+
+            fifo_submit(self.mgr.as_ptr(), node);
+            true
+        }
+    }
+
+    /// 按页遍历 `prefix` 下的所有条目，返回一个 [`TraverseStream`]
+    ///
+    /// 每次 `poll_next` 内部会提交一次 `submit_traverse`/`submit_traverse_from`
+    /// 请求（游标是上一页最后一条结果的 key），收集到 `page_size` 条结果或
+    /// 遇到 `TraverseEnd` 就返回这一页；一页结果数少于 `page_size`（包括 0）
+    /// 说明已经遍历完——非空的那一页仍会正常产出，空页则直接结束流而不产出。
+    pub fn traverse_stream<'a>(
+        &'a self,
+        prefix: &'a [u8],
+        version: u64,
+        page_size: u32,
+    ) -> TraverseStream<'a> {
+        TraverseStream {
+            fifo: self,
+            prefix,
+            version,
+            page_size,
+            after_key: None,
+            done: false,
+        }
+    }
+
     /// 轮询完成（非阻塞）
     pub fn poll(&self) -> Option<FindResult> {
         unsafe {
@@ -318,19 +846,42 @@ impl AsyncFifo {
     fn node_to_result(&self, node: *mut CompletionNode) -> FindResult {
         unsafe {
             let comp = &(*node).comp;
+            let user_data = combine_ud(comp.user_data_lo, comp.user_data_hi);
+            let status = ResultStatus::from(comp.status);
+            let mut value = if comp.value_len > 0 && comp.value_len != 0xFFFFFFFF {
+                Some(comp.value[..comp.value_len as usize].to_vec())
+            } else {
+                None
+            };
+
+            if let Some(default_value) = self.take_default_for(user_data) {
+                if status == ResultStatus::NotFound {
+                    value = Some(default_value.to_vec());
+                }
+            }
+
             FindResult {
-                user_data: combine_ud(comp.user_data_lo, comp.user_data_hi),
-                status: ResultStatus::from(comp.status),
-                value: if comp.value_len > 0 && comp.value_len != 0xFFFFFFFF {
-                    Some(comp.value[..comp.value_len as usize].to_vec())
+                user_data,
+                status,
+                value,
+                has_large_value: comp.value_len == 0xFFFFFFFF,
+                merkle_hash: comp.merkle_hash,
+                proof: if comp.proof_len > 0 && comp.proof_len != 0xFFFFFFFF {
+                    Some(vec![comp.proof_data[..comp.proof_len as usize].to_vec()])
                 } else {
                     None
                 },
-                has_large_value: comp.value_len == 0xFFFFFFFF,
-                merkle_hash: comp.merkle_hash,
+                has_large_proof: comp.proof_len == 0xFFFFFFFF,
             }
         }
     }
+
+    /// 取出并移除 `user_data` 对应的 [`AsyncFifo::submit_find_or_default`]
+    /// 默认值（如果有）
+    fn take_default_for(&self, user_data: u128) -> Option<[u8; 32]> {
+        let mut defaults = self.defaults.lock().expect("AsyncFifo defaults mutex poisoned");
+        defaults.remove(&user_data)
+    }
     
     /// 轮询大值（非阻塞）
     pub fn poll_large_value(&self) -> Option<LargeValue> {
@@ -353,7 +904,67 @@ impl AsyncFifo {
             Some(result)
         }
     }
-    
+
+    /// 轮询大值，但不立即拷贝整个 `Vec<u8>`，而是返回一个可以按需
+    /// `Read::read` 的 [`LargeValueStream`]
+    ///
+    /// `LargeValueNode` 的数据本身就是紧跟在结构体后面的一段连续内存
+    /// （`poll_large_value` 也是这么读的），所以这里不需要新增一个
+    /// `fifo_poll_large_value_chunk` 之类的 C++ 函数专门分段搬运数据——
+    /// Rust 侧已经能直接按偏移量切片，新增一次 FFI 往返只会多一层开销。
+    pub fn poll_large_value_stream(&self) -> Option<(u128, LargeValueStream)> {
+        unsafe {
+            let node = fifo_poll_large_value(self.mgr.as_ptr());
+            if node.is_null() {
+                return None;
+            }
+
+            let user_data = combine_ud((*node).user_data_lo, (*node).user_data_hi);
+            let len = (*node).len as usize;
+
+            Some((
+                user_data,
+                LargeValueStream { mgr: self.mgr.as_ptr(), node, len, offset: 0 },
+            ))
+        }
+    }
+
+    /// 按 `interval` 持续轮询 [`AsyncFifo::poll_large_value`]，每当有大值
+    /// 到达就产出一条 [`LargeValue`]
+    pub fn large_value_stream(&self, interval: Duration) -> LargeValueWatch<'_> {
+        LargeValueWatch { fifo: self, interval }
+    }
+
+    /// 自旋等待一个特定 `user_data` 对应的大值，超时返回 `None`
+    ///
+    /// 轮询过程中遇到的、不是 `user_data` 这一个的大值会先存进 `buffer`
+    /// （见 [`LargeValueBuffer`]），下次针对那个 `user_data` 调用这个方法
+    /// 时可以直接从 `buffer` 里取到，而不需要它刚好还留在完成队列里。
+    pub fn take_large_value_for(
+        &self,
+        user_data: u128,
+        timeout: Duration,
+        buffer: &mut LargeValueBuffer,
+    ) -> Option<LargeValue> {
+        if let Some(value) = buffer.take(user_data) {
+            return Some(value);
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.poll_large_value() {
+                Some(value) if value.user_data == user_data => return Some(value),
+                Some(value) => buffer.insert(value),
+                None => {
+                    if Instant::now() >= deadline {
+                        return None;
+                    }
+                    std::thread::sleep(Duration::from_micros(50));
+                }
+            }
+        }
+    }
+
     // === 批量操作 ===
     
     /// 批量提交 find 请求
@@ -439,24 +1050,298 @@ impl AsyncFifo {
         if max == 0 {
             return vec![];
         }
-        
+
         let mut nodes: Vec<*mut CompletionNode> = vec![std::ptr::null_mut(); max];
         let mut results = Vec::new();
-        
+
         unsafe {
             let count = fifo_poll_traverse_batch(self.mgr.as_ptr(), nodes.as_mut_ptr(), max);
-            
+
             for i in 0..count {
                 if !nodes[i].is_null() {
                     results.push(self.node_to_result(nodes[i]));
                 }
             }
-            
+
             fifo_free_traverse_batch(self.mgr.as_ptr(), nodes.as_ptr(), count);
         }
-        
+
         results
     }
+
+    // === 批量消费 ===
+
+    /// 每次批量轮询使用的窗口大小
+    const DRAIN_BATCH_SIZE: usize = 256;
+
+    /// 一次性消费完成队列中的所有结果
+    ///
+    /// 循环调用 `poll_batch` 直到队列为空，适合 stop-the-world 式的收尾处理。
+    pub fn drain_completions(&self) -> Vec<FindResult> {
+        let mut all = Vec::new();
+        loop {
+            let batch = self.poll_batch(Self::DRAIN_BATCH_SIZE);
+            if batch.is_empty() {
+                break;
+            }
+            all.extend(batch);
+        }
+        all
+    }
+
+    /// 一次性消费 Traverse 队列中的所有结果
+    pub fn drain_traversals(&self) -> Vec<FindResult> {
+        let mut all = Vec::new();
+        loop {
+            let batch = self.poll_traverse_batch(Self::DRAIN_BATCH_SIZE);
+            if batch.is_empty() {
+                break;
+            }
+            all.extend(batch);
+        }
+        all
+    }
+
+    /// 自旋等待直到收集到 `count` 个完成结果或超时
+    ///
+    /// 超时后返回 `Err(WaitTimeoutError)`，其中携带已经收到的结果数量，
+    /// 便于调用方判断是重试还是放弃。
+    pub fn wait_for_count(
+        &self,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<Vec<FindResult>, WaitTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut results = Vec::with_capacity(count);
+
+        while results.len() < count {
+            let remaining = count - results.len();
+            let batch = self.poll_batch(remaining.max(1));
+            if batch.is_empty() {
+                if Instant::now() >= deadline {
+                    return Err(WaitTimeoutError {
+                        received: results.len(),
+                        expected: count,
+                    });
+                }
+                std::thread::sleep(Duration::from_micros(50));
+                continue;
+            }
+            results.extend(batch);
+        }
+
+        Ok(results)
+    }
+
+    /// 提交一批 find 请求并等待全部结果到达（或超时），按 `user_data` 对齐
+    /// 请求和结果——和 [`AsyncFifo::wait_for_count`] 的区别是那个方法不关心
+    /// "收到的是不是我刚提交的那些"，这个方法要求结果集合和 `requests` 的
+    /// `user_data` 集合完全一致
+    ///
+    /// 内部调用 [`AsyncFifo::submit_find_batch`] 一次性提交全部请求，再用
+    /// 一个 `HashMap<user_data, FindResult>` 在 sleep 轮询循环里收集完成
+    /// 结果，直到收满或者 `timeout` 耗尽。超时时返回的 [`BatchError`] 带着
+    /// 已经收到的部分结果，调用方可以自己决定重试剩下的 `missing_user_data`
+    /// 还是直接放弃。
+    pub fn batch_find(
+        &self,
+        requests: Vec<FindRequest>,
+        timeout: Duration,
+    ) -> Result<Vec<FindResult>, BatchError> {
+        let expected: HashSet<u128> = requests.iter().map(|r| r.user_data).collect();
+        let tuples: Vec<(&[u8], u64, u128)> = requests
+            .iter()
+            .map(|r| (r.key.as_slice(), r.version, r.user_data))
+            .collect();
+        self.submit_find_batch(&tuples);
+
+        let mut received: HashMap<u128, FindResult> = HashMap::with_capacity(expected.len());
+        let deadline = Instant::now() + timeout;
+
+        while received.len() < expected.len() {
+            let remaining = expected.len() - received.len();
+            let batch = self.poll_batch(remaining);
+            if batch.is_empty() {
+                if Instant::now() >= deadline {
+                    break;
+                }
+                std::thread::sleep(Duration::from_micros(50));
+                continue;
+            }
+            for result in batch {
+                if expected.contains(&result.user_data) {
+                    received.insert(result.user_data, result);
+                }
+            }
+        }
+
+        if received.len() == expected.len() {
+            Ok(expected.iter().filter_map(|ud| received.remove(ud)).collect())
+        } else {
+            let missing_user_data = expected
+                .iter()
+                .copied()
+                .filter(|ud| !received.contains_key(ud))
+                .collect();
+            Err(BatchError {
+                received: received.into_values().collect(),
+                missing_user_data,
+            })
+        }
+    }
+}
+
+/// [`AsyncFifo::batch_find`] 的单条请求：key/version/user_data 三元组的
+/// 具名版本，比裸元组更自文档化
+#[derive(Debug, Clone)]
+pub struct FindRequest {
+    pub key: Vec<u8>,
+    pub version: u64,
+    pub user_data: u128,
+}
+
+impl FindRequest {
+    pub fn new(key: impl Into<Vec<u8>>, version: u64, user_data: u128) -> Self {
+        Self {
+            key: key.into(),
+            version,
+            user_data,
+        }
+    }
+}
+
+/// [`AsyncFifo::batch_find`] 超时错误：带着已经收到的部分结果，而不是把它们
+/// 直接丢掉
+#[derive(Debug, Clone)]
+pub struct BatchError {
+    /// 超时前已经收到、且确实属于这批请求的结果
+    pub received: Vec<FindResult>,
+    /// 还没收到结果的请求的 `user_data`
+    pub missing_user_data: Vec<u128>,
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "batch_find timed out: received {}, missing {}",
+            self.received.len(),
+            self.missing_user_data.len()
+        )
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// `wait_for_count` 超时错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeoutError {
+    /// 超时前已经收到的结果数量
+    pub received: usize,
+    /// 期望收到的结果数量
+    pub expected: usize,
+}
+
+impl std::fmt::Display for WaitTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "timed out waiting for {} results, received {}",
+            self.expected, self.received
+        )
+    }
+}
+
+impl std::error::Error for WaitTimeoutError {}
+
+/// [`AsyncFifo::traverse_stream`] 遇到的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraverseError {
+    /// 请求提交失败（内存分配失败）
+    SubmitFailed,
+    /// 引擎对某一条目返回了 `ResultStatus::Error`
+    EngineError,
+}
+
+impl std::fmt::Display for TraverseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SubmitFailed => write!(f, "failed to submit traverse request"),
+            Self::EngineError => write!(f, "traverse request returned an error status"),
+        }
+    }
+}
+
+impl std::error::Error for TraverseError {}
+
+/// [`AsyncFifo::traverse_stream`] 返回的分页遍历流
+///
+/// 注意：底层完成队列没有真正的异步唤醒机制（`wait_for_count` 也是一样），
+/// 所以 `poll_next` 在拿到一整页结果之前会自旋阻塞当前线程，并不是"真异步"
+/// 的——这里只是把既有的分页轮询逻辑包装成了 `Stream` 接口，方便用
+/// `futures` 的组合子（`next()`/`try_collect()` 等）消费。
+pub struct TraverseStream<'a> {
+    fifo: &'a AsyncFifo,
+    prefix: &'a [u8],
+    version: u64,
+    page_size: u32,
+    after_key: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl Stream for TraverseStream<'_> {
+    type Item = Result<Vec<FindResult>, TraverseError>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        const USER_DATA: u128 = 0;
+        let submitted = match &this.after_key {
+            None => this.fifo.submit_traverse(this.prefix, this.version, this.page_size, USER_DATA),
+            Some(after_key) => this.fifo.submit_traverse_from(
+                this.prefix,
+                this.version,
+                after_key,
+                this.page_size,
+                USER_DATA,
+            ),
+        };
+        if !submitted {
+            this.done = true;
+            return Poll::Ready(Some(Err(TraverseError::SubmitFailed)));
+        }
+
+        let mut page = Vec::new();
+        loop {
+            match this.fifo.poll_traverse() {
+                Some(result) => match result.status {
+                    ResultStatus::TraverseEnd => break,
+                    ResultStatus::Error => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(TraverseError::EngineError)));
+                    }
+                    _ => page.push(result),
+                },
+                None => std::thread::sleep(Duration::from_micros(50)),
+            }
+        }
+
+        if page.is_empty() {
+            this.done = true;
+            return Poll::Ready(None);
+        }
+
+        if page.len() < this.page_size as usize {
+            this.done = true;
+        } else {
+            this.after_key = page.last().map(|r| r.merkle_hash.to_vec());
+        }
+
+        Poll::Ready(Some(Ok(page)))
+    }
 }
 
 impl Drop for AsyncFifo {
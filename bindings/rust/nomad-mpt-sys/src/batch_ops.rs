@@ -0,0 +1,64 @@
+//! 多来源 [`Update`] 批次的合并 - 比如同一个区块里多笔交易各自产出的更新
+
+use crate::Update;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 合并 `base` 和 `overlay` 两批更新，冲突时 `overlay` 赢（last-write-wins）
+///
+/// 两边都出现的 key 只保留 `overlay` 那条——包括 `overlay` 里的删除，它会
+/// 覆盖 `base` 里对同一个 key 的写入。`nested` 更新按同样的策略递归合并：
+/// 只有两边都有 `nested` 时才会递归，否则直接沿用那一侧自己的 `nested`。
+///
+/// 用 `BTreeMap<&[u8], Update>` 做合并，插入和覆盖都是 `O(log n)`，整体是
+/// `O((m+n) log n)`，其中 `n`、`m` 分别是 `base`、`overlay` 的长度。返回值
+/// 按 key 的字节序排列。
+pub fn merge_updates<'a>(base: &[Update<'a>], overlay: &[Update<'a>]) -> Vec<Update<'a>> {
+    let mut merged: BTreeMap<&'a [u8], Update<'a>> = BTreeMap::new();
+
+    for update in base {
+        merged.insert(update.key, update.clone());
+    }
+    for update in overlay {
+        match merged.remove(update.key) {
+            Some(base_update) if !base_update.nested.is_empty() && !update.nested.is_empty() => {
+                let mut merged_update = update.clone();
+                merged_update.nested = merge_updates(&base_update.nested, &update.nested);
+                merged.insert(update.key, merged_update);
+            }
+            _ => {
+                merged.insert(update.key, update.clone());
+            }
+        }
+    }
+
+    merged.into_values().collect()
+}
+
+/// 把 `map` 转成按 key 字节序排好的 [`Update`] 批次
+///
+/// `HashMap` 本身的遍历顺序不确定，同一批更新每次跑出来的插入顺序都可能
+/// 不一样；排序之后结果是确定的，方便测试/日志比较，也让调用方不用自己
+/// 在插入 trie 之前另外排一遍。至于排序本身是否真的会让 trie 插入更快：
+/// 这个仓库现有的写路径（`Db::upsert`/`ffi::db_upsert`）都是整批一次性
+/// 交给引擎，底层 `mpt::Db::upsert` 内部本来就会按 key 重新组织更新列表
+/// 构建/合并 trie 节点——所以这里的排序主要是为了确定性输出，不是为了
+/// 绕开一个引擎没做的优化。
+pub fn updates_from_hashmap<'a>(map: &'a HashMap<Vec<u8>, Vec<u8>>) -> Vec<Update<'a>> {
+    let mut updates: Vec<Update<'a>> =
+        map.iter().map(|(key, value)| Update::put(key, value)).collect();
+    updates.sort_by(|a, b| a.key.cmp(b.key));
+    updates
+}
+
+/// 和 [`updates_from_hashmap`] 一样，但 `map` 本身已经按 key 字节序排好，
+/// 不需要再排一遍
+pub fn updates_from_btreemap<'a>(map: &'a BTreeMap<Vec<u8>, Vec<u8>>) -> Vec<Update<'a>> {
+    map.iter().map(|(key, value)| Update::put(key, value)).collect()
+}
+
+/// 把 `keys` 转成按字节序排好的 [`Update::delete`] 批次
+pub fn delete_updates_from_set<'a>(keys: &'a HashSet<Vec<u8>>) -> Vec<Update<'a>> {
+    let mut updates: Vec<Update<'a>> = keys.iter().map(|key| Update::delete(key)).collect();
+    updates.sort_by(|a, b| a.key.cmp(b.key));
+    updates
+}
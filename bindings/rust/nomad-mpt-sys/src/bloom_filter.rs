@@ -0,0 +1,116 @@
+//! 手写的 bit-array bloom filter - [`crate::DbConfig::with_bloom_index_path`] 用
+//!
+//! 没有引入 `bloomfilter`/`probabilistic-collections` 之类的专门 crate——
+//! 这个仓库目前没有任何概率数据结构依赖，引入它会是第一个、也是唯一一个
+//! 用到的，没有先例（和之前拒绝引入 `lru`/`rayon`/`blake3` 是同一类理由）。
+//! 哈希复用已有的 [`crate::keccak256`]，用哈希轮次号做 domain separation
+//! 代替教科书里的双重哈希（`h1 + i*h2`）——效果等价，不需要实现第二个
+//! 独立的哈希函数。
+
+use crate::keccak256;
+
+/// 固定容量的 bit-array bloom filter
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    num_bits: usize,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// 创建一个按 `expected_items` 条目、`false_positive_rate` 误报率预先
+    /// 算好大小的空过滤器
+    ///
+    /// 位数组大小和哈希函数个数用标准公式算出来（`m = -n*ln(p)/ln(2)^2`，
+    /// `k = (m/n)*ln(2)`），插入之后固定不变——插入的实际条目数超过
+    /// `expected_items` 时，真实误报率会比 `false_positive_rate` 更高，这
+    /// 是所有固定大小 bloom filter 共有的限制，这里没有做动态扩容/rebuild。
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let expected_items = expected_items.max(1);
+        let num_bits = optimal_num_bits(expected_items, false_positive_rate).max(8);
+        let num_hashes = optimal_num_hashes(num_bits, expected_items).max(1);
+        BloomFilter { bits: vec![0u8; num_bits.div_ceil(8)], num_bits, num_hashes }
+    }
+
+    /// 把 `key` 加入过滤器
+    pub fn insert(&mut self, key: &[u8]) {
+        for round in 0..self.num_hashes {
+            let idx = self.bit_index(key, round);
+            self.bits[idx / 8] |= 1 << (idx % 8);
+        }
+    }
+
+    /// `key` 是否*可能*在过滤器里——`false` 是确定的（一定不在），`true`
+    /// 只是可能（可能是误报）
+    pub fn contains(&self, key: &[u8]) -> bool {
+        (0..self.num_hashes).all(|round| {
+            let idx = self.bit_index(key, round);
+            self.bits[idx / 8] & (1 << (idx % 8)) != 0
+        })
+    }
+
+    fn bit_index(&self, key: &[u8], round: u32) -> usize {
+        let mut buf = Vec::with_capacity(4 + key.len());
+        buf.extend_from_slice(&round.to_le_bytes());
+        buf.extend_from_slice(key);
+        let hash = keccak256(&buf);
+        let h = u64::from_le_bytes(hash[0..8].try_into().expect("keccak256 output is 32 bytes"));
+        (h % self.num_bits as u64) as usize
+    }
+
+    /// 序列化成字节：`num_bits`(u64 LE) + `num_hashes`(u64 LE) + 位数组本身
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.bits.len());
+        out.extend_from_slice(&(self.num_bits as u64).to_le_bytes());
+        out.extend_from_slice(&(self.num_hashes as u64).to_le_bytes());
+        out.extend_from_slice(&self.bits);
+        out
+    }
+
+    /// [`BloomFilter::serialize`] 的逆操作
+    pub fn deserialize(data: &[u8]) -> Result<Self, BloomFilterError> {
+        if data.len() < 16 {
+            return Err(BloomFilterError::Truncated);
+        }
+        let num_bits = u64::from_le_bytes(data[0..8].try_into().unwrap()) as usize;
+        let num_hashes = u64::from_le_bytes(data[8..16].try_into().unwrap()) as u32;
+        let bits = &data[16..];
+        if bits.len() != num_bits.div_ceil(8) {
+            return Err(BloomFilterError::Truncated);
+        }
+        Ok(BloomFilter { bits: bits.to_vec(), num_bits, num_hashes })
+    }
+}
+
+fn optimal_num_bits(expected_items: usize, false_positive_rate: f64) -> usize {
+    let n = expected_items as f64;
+    let m = -(n * false_positive_rate.ln()) / std::f64::consts::LN_2.powi(2);
+    m.ceil() as usize
+}
+
+fn optimal_num_hashes(num_bits: usize, expected_items: usize) -> u32 {
+    let k = (num_bits as f64 / expected_items as f64) * std::f64::consts::LN_2;
+    k.round() as u32
+}
+
+/// [`BloomFilter::deserialize`] 失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloomFilterError {
+    /// 字节数不够构成一个合法的头部 + 位数组
+    Truncated,
+}
+
+impl std::fmt::Display for BloomFilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BloomFilterError::Truncated => write!(f, "truncated bloom filter bytes"),
+        }
+    }
+}
+
+impl std::error::Error for BloomFilterError {}
+
+/// [`crate::DbConfig::with_bloom_index_path`] 用的默认容量假设
+pub const DEFAULT_BLOOM_EXPECTED_ITEMS: usize = 1_000_000;
+/// [`crate::DbConfig::with_bloom_index_path`] 用的目标误报率
+pub const DEFAULT_BLOOM_FALSE_POSITIVE_RATE: f64 = 0.001;
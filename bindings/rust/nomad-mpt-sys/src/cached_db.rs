@@ -0,0 +1,183 @@
+//! find 结果缓存 - 避免对热点 key（比如系统合约）反复发起 FFI 调用
+
+use crate::hot_keys::decode_hex_line;
+use crate::{Db, Error, Node, Update};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// [`CachedDb::cache_stats`] 返回的计数器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// 固定容量、按最近使用顺序淘汰的缓存
+///
+/// 没有引入 `lru` 这样的专门 crate：和 [`crate::hot_keys::HotKeyTracker`]
+/// 对"要不要引入专门的并发/缓存数据结构"这个问题的处理方式一样，这个仓库
+/// 目前没有任何模块依赖过 `lru`，而一个 `HashMap` 加一条记录访问顺序的
+/// `VecDeque` 已经足够实现 [`CachedDb`] 需要的淘汰语义——如果以后发现这里
+/// 的 `O(n)` 重排成了瓶颈，再按需引入专门的 crate 替换，调用方的 API 不
+/// 需要变。
+struct LruMap<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + std::hash::Hash + Clone, V> LruMap<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), entries: HashMap::new(), order: VecDeque::new() }
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).expect("position just found");
+            self.order.push_back(key);
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(key)
+    }
+
+    /// 插入一条缓存项，返回这次插入是否淘汰了一条旧的
+    fn insert(&mut self, key: K, value: V) -> bool {
+        if self.entries.contains_key(&key) {
+            self.touch(&key);
+            self.entries.insert(key, value);
+            return false;
+        }
+
+        let evicted = if self.entries.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+            true
+        } else {
+            false
+        };
+
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+        evicted
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+        self.order.retain(|key| self.entries.contains_key(key));
+    }
+}
+
+/// 包了一层 find 结果缓存的 [`Db`]，见 [`CachedDb::new`]
+pub struct CachedDb {
+    db: Db,
+    cache: Mutex<LruMap<(Vec<u8>, u64), Option<Vec<u8>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CachedDb {
+    /// 包一层容量为 `capacity` 的 find 结果缓存
+    pub fn new(db: Db, capacity: usize) -> Self {
+        Self {
+            db,
+            cache: Mutex::new(LruMap::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// 查找 key 对应的值，命中缓存时不会发起 FFI 调用；其余语义见
+    /// [`Db::find`]
+    pub fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        let cache_key = (key.to_vec(), version);
+
+        {
+            let mut cache = self.cache.lock().expect("CachedDb mutex poisoned");
+            if let Some(value) = cache.get(&cache_key) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(value.clone());
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = self.db.find(key, version)?;
+
+        let mut cache = self.cache.lock().expect("CachedDb mutex poisoned");
+        if cache.insert(cache_key, value.clone()) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(value)
+    }
+
+    /// 写入新的一批更新；所有版本号 `<= version` 的缓存项会被清掉，其余
+    /// 语义见 [`Db::upsert`]
+    pub fn upsert(&mut self, updates: &[Update], version: u64) -> Result<Node, cxx::Exception> {
+        let root = self.db.upsert(updates, version)?;
+        let mut cache = self.cache.lock().expect("CachedDb mutex poisoned");
+        cache.retain(|(_, cached_version)| *cached_version > version);
+        Ok(root)
+    }
+
+    /// 返回迄今为止的命中/未命中/淘汰次数
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 取回底层的 [`Db`]，丢弃缓存内容和统计数字
+    pub fn into_inner(self) -> Db {
+        self.db
+    }
+
+    /// 对 `hot_keys` 里的每个 key 发起一次 [`CachedDb::find`]，把结果预先
+    /// 填入缓存；返回其中找到了值的 key 数
+    ///
+    /// 这个方法原本是想加在 [`Db`] 上的（`Db::warm_cache`），但 `Db` 本身
+    /// 没有缓存可"热"——这个仓库里唯一真正的 find 结果缓存就是
+    /// [`CachedDb`]，`find` 本身已经会把结果塞进缓存，所谓"预热"不过是替
+    /// 调用方提前调用一遍，所以直接把这个方法放在它真正的主人 [`CachedDb`]
+    /// 上，而不是在 `Db` 上伪造一个不存在的缓存层。
+    pub fn warm_cache(&mut self, hot_keys: &[Vec<u8>], version: u64) -> Result<usize, Error> {
+        let mut found = 0;
+        for key in hot_keys {
+            if self.find(key, version)?.is_some() {
+                found += 1;
+            }
+        }
+        Ok(found)
+    }
+
+    /// 从换行分隔的十六进制 key 文件读取 key 列表，逐个 [`CachedDb::warm_cache`]；
+    /// 格式见 [`crate::hot_keys::TrackedDb::dump_hot_keys_to_file`]
+    ///
+    /// 无法解析成合法十六进制的行会被跳过，不会中断整个预热过程——文件可能
+    /// 是手写的或者来自旧版本的 dump 格式，容错比严格校验更符合"最好努力"
+    /// 的预热语义。
+    pub fn warm_cache_from_file(&mut self, path: impl AsRef<Path>, version: u64) -> Result<usize, Error> {
+        let contents = std::fs::read_to_string(path).map_err(Error::Io)?;
+        let hot_keys: Vec<Vec<u8>> = contents.lines().filter_map(decode_hex_line).collect();
+        self.warm_cache(&hot_keys, version)
+    }
+}
+
+impl Db {
+    /// 包一层 find 结果缓存，见 [`CachedDb::new`]
+    pub fn with_find_cache(self, capacity: usize) -> CachedDb {
+        CachedDb::new(self, capacity)
+    }
+}
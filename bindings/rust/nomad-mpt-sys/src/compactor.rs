@@ -0,0 +1,106 @@
+//! 后台周期性压实任务，见 [`BackgroundCompactor::start`]
+//!
+//! 请求里要的是一个独立的 `Db::compact` 入口；这个裁剪后的代码树里
+//! `mpt::Db`（见 `depend/monad/category/mpt/db.hpp`）没有暴露这样一个
+//! 手动触发的压实/compaction API——磁盘模式下的节点回收完全是引擎内部
+//! 行为（随 [`crate::Db::update_finalized_version`] 自动发生），这也是
+//! [`crate::Db::gc`] 在磁盘模式下直接返回 `Error::Unsupported` 的原因。
+//! 这里能做的最接近的真实操作，就是按固定周期主动调用已经存在的
+//! `Db::gc(db.latest_version())`：内存模式下这是一次真实的内存回收；磁盘
+//! 模式下每一轮都会拿到 `Error::Unsupported`，这里不会把它当成致命错误
+//! panic，只是记一条 `tracing::warn!` 然后等下一个周期重试。
+
+use crate::Db;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 周期性压实任务的配置，见 [`BackgroundCompactor::start`]
+pub struct BackgroundCompactor {
+    interval: Duration,
+}
+
+impl BackgroundCompactor {
+    /// 每隔 `interval` 触发一次压实
+    pub fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+
+    /// 启动后台线程，返回可以用来停止它、查询上一次压实耗时的 [`CompactorHandle`]
+    pub fn start(self, db: Arc<Mutex<Db>>) -> CompactorHandle {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_for_thread = Arc::clone(&shutdown);
+        let last_duration_nanos = Arc::new(AtomicU64::new(0));
+        let last_duration_for_thread = Arc::clone(&last_duration_nanos);
+        let interval = self.interval;
+
+        let thread = std::thread::spawn(move || {
+            while !shutdown_for_thread.load(Ordering::Relaxed) {
+                std::thread::sleep(interval);
+                if shutdown_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+                run_one_compaction(&db, &last_duration_for_thread);
+            }
+        });
+
+        CompactorHandle { shutdown, thread: Some(thread), last_duration_nanos }
+    }
+}
+
+fn run_one_compaction(db: &Arc<Mutex<Db>>, last_duration_nanos: &Arc<AtomicU64>) {
+    let started = Instant::now();
+    let result = {
+        let mut db = db.lock().expect("BackgroundCompactor db mutex poisoned");
+        let version = db.latest_version();
+        db.gc(version)
+    };
+    let elapsed = started.elapsed();
+
+    match result {
+        Ok(freed_bytes) => {
+            last_duration_nanos.store(elapsed.as_nanos() as u64, Ordering::Relaxed);
+            tracing::info!(freed_bytes, elapsed_ms = elapsed.as_millis() as u64, "background compaction finished");
+        }
+        Err(err) => tracing::warn!(%err, "background compaction skipped"),
+    }
+}
+
+/// [`BackgroundCompactor::start`] 返回的 handle
+pub struct CompactorHandle {
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    last_duration_nanos: Arc<AtomicU64>,
+}
+
+impl CompactorHandle {
+    /// 发出停止信号并等待后台线程退出
+    ///
+    /// 线程每次只在 `sleep(interval)` 之间检查一次停止信号，最坏情况下
+    /// 这里会阻塞接近一个 `interval`，和 [`crate::FifoMetricsCollector::stop`]
+    /// 的限制一样。
+    pub fn stop(self) {
+        // Drop 会做同样的事，这里显式调用是为了让调用方能等到线程真正退出
+        // 再继续往下走，而不是依赖析构的时机。
+    }
+
+    /// 最近一轮压实耗费的时间；从未成功跑过一轮时返回 `None`
+    pub fn last_compact_duration(&self) -> Option<Duration> {
+        let nanos = self.last_duration_nanos.load(Ordering::Relaxed);
+        if nanos == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(nanos))
+        }
+    }
+}
+
+impl Drop for CompactorHandle {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! 持续订阅版本变化、按需产出每个新版本的差异 - 见 [`Db::diff_stream`]
+
+use crate::{Db, Error};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+
+/// 一次版本差异里的条目迭代器
+///
+/// # 未实现
+/// 见 [`crate::DiffEntry`] 文档里已经说明的限制：这个裁剪后的代码树没有
+/// `Db::diff`/`db_diff`（计算两个版本之间差异的那一侧），只有
+/// [`Db::apply_diff`] 这个"应用"侧。所以这里没有数据源可以填充具体改动的
+/// key，`DiffIter` 目前总是空的——`DiffStream` 能如实反映"版本变了"和
+/// "变成了哪个版本号"，但给不出"具体哪些 key 变了"。
+pub struct DiffIter {
+    entries: std::vec::IntoIter<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl Iterator for DiffIter {
+    type Item = (Vec<u8>, Option<Vec<u8>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+/// [`Db::diff_stream`] 返回的流：每当 `latest_version()` 前进，就产出一条
+/// `(新版本号, DiffIter)`
+///
+/// 和 [`crate::HealthWatch`] 一样，底层引擎没有真正的异步唤醒机制，
+/// `poll_next` 用短暂 `sleep` 轮询 `latest_version()` 模拟——这不是严格意义
+/// 上的事件驱动，但足以在 `tokio` 的 `Stream` 生态里复用现成的 combinator。
+pub struct DiffStream<'a> {
+    db: &'a Db,
+    last_seen: u64,
+}
+
+impl<'a> DiffStream<'a> {
+    pub(crate) fn new(db: &'a Db) -> Self {
+        Self { db, last_seen: u64::MAX }
+    }
+}
+
+impl<'a> Stream for DiffStream<'a> {
+    type Item = Result<(u64, DiffIter), Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let latest = this.db.latest_version();
+            if latest != u64::MAX && latest != this.last_seen {
+                this.last_seen = latest;
+                let diff = DiffIter { entries: Vec::new().into_iter() };
+                return Poll::Ready(Some(Ok((latest, diff))));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
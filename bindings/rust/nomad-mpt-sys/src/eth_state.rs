@@ -0,0 +1,101 @@
+//! 以太坊风格状态根的计算 - `keccak256(address) -> RLP(account)`
+//!
+//! 这不是引擎本身提供的能力，只是在现有的 [`Db`]、[`crate::keccak256`] 和
+//! `proof` 模块的 RLP 编码器之上拼出来的一个便捷封装：对每个账户按以太坊的
+//! 约定算出 key/value，插进一个内存 [`Db`]，再读它的 [`crate::Node::root_hash`]。
+//!
+//! 没有针对以太坊主网的真实数据做过交叉验证——这需要主网 genesis 的账户
+//! 全集，这个仓库里没有，在这个环境里也没法联网下载，所以没法在测试里断言
+//! "算出来的根等于某个已知的主网状态根"。测试里用的是一组自造的小账户集合，
+//! 只验证这个函数本身是确定性的、和直接手写 RLP + `Db::upsert` 算出来的结果
+//! 一致，不代表已经对上了任何真实链上数据。
+
+use crate::proof::{encode_rlp_bytes, encode_rlp_list_header};
+use crate::{keccak256, Db, Update};
+
+/// 以太坊地址：20 字节
+pub type Address = [u8; 20];
+
+/// 256 位无符号整数，大端字节序存储——跟以太坊 RLP/ABI 里的惯例一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct U256(pub [u8; 32]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0u8; 32]);
+
+    pub fn from_u64(value: u64) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&value.to_be_bytes());
+        U256(bytes)
+    }
+
+    /// 大端字节序的完整 32 字节表示
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// RLP 编码整数要用的最小大端表示：去掉前导零；0 编码成空串
+    fn to_minimal_be(&self) -> &[u8] {
+        match self.0.iter().position(|&b| b != 0) {
+            Some(i) => &self.0[i..],
+            None => &[],
+        }
+    }
+}
+
+/// 以太坊账户状态：`nonce`、`balance`、合约代码哈希、存储树根
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountState {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code_hash: [u8; 32],
+    pub storage_root: [u8; 32],
+}
+
+impl AccountState {
+    /// RLP 编码成以太坊状态树里账户叶子节点的 value：
+    /// `[nonce, balance, storageRoot, codeHash]`
+    fn to_rlp(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        encode_rlp_bytes(&minimal_be_u64(self.nonce), &mut payload);
+        encode_rlp_bytes(self.balance.to_minimal_be(), &mut payload);
+        encode_rlp_bytes(&self.storage_root, &mut payload);
+        encode_rlp_bytes(&self.code_hash, &mut payload);
+
+        let mut out = Vec::new();
+        encode_rlp_list_header(payload.len(), &mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+}
+
+fn minimal_be_u64(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    match bytes.iter().position(|&b| b != 0) {
+        Some(i) => bytes[i..].to_vec(),
+        None => Vec::new(),
+    }
+}
+
+/// 计算 `accounts` 的"以太坊风格"状态根
+///
+/// key 是 `keccak256(address)`，value 是 [`AccountState::to_rlp`]，都插进一个
+/// 临时的内存 [`Db`]，返回它在插入所有账户后的 `root_hash()`。`accounts`
+/// 里出现重复地址时，后面的条目会覆盖前面的（和 [`Db::upsert`] 本身对同一
+/// key 多次写入的语义一致）。
+pub fn compute_ethereum_state_root(accounts: &[(Address, AccountState)]) -> [u8; 32] {
+    let mut db = Db::open_memory()
+        .expect("compute_ethereum_state_root: failed to open in-memory db");
+
+    let entries: Vec<([u8; 32], Vec<u8>)> = accounts
+        .iter()
+        .map(|(address, account)| (keccak256(address), account.to_rlp()))
+        .collect();
+    let updates: Vec<Update> =
+        entries.iter().map(|(key, value)| Update::put(key, value)).collect();
+
+    let root = db
+        .upsert(&updates, 1)
+        .expect("compute_ethereum_state_root: upsert into a fresh in-memory db failed");
+    root.root_hash()
+}
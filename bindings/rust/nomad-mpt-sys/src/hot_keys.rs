@@ -0,0 +1,109 @@
+//! 热点 key 统计 - 为预取/性能调优提供"最常被访问的 key"报告
+
+use crate::{Db, Error};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 把 `bytes` 编码成小写十六进制字符串
+///
+/// [`proof::to_hex`](crate::proof) 做的是同样的编码（多一个 `0x` 前缀），但它是给 Ethereum
+/// JSON-RPC 的十六进制字符串格式用的，藏在 `#[cfg(feature = "serde")]`
+/// 后面——把这里的换行分隔 key 文件格式套在 `serde` feature 上并不合适，
+/// 所以单独写一份不带 feature gate 的版本，供 [`TrackedDb::dump_hot_keys_to_file`]
+/// 和 [`crate::cached_db::CachedDb::warm_cache_from_file`] 共用。
+pub(crate) fn encode_hex_line(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// [`encode_hex_line`] 的逆操作
+pub(crate) fn decode_hex_line(line: &str) -> Option<Vec<u8>> {
+    if line.is_empty() || line.len() % 2 != 0 {
+        return None;
+    }
+    (0..line.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&line[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// [`Db::with_hot_key_tracking`] 用到的访问计数器
+///
+/// 用一个 `Mutex<BTreeMap<..>>` 而不是专门的并发 map（比如 DashMap）：这
+/// 个仓库目前没有引入任何并发 map 依赖，而热点 key 统计本身不在 `find`
+/// 的性能关键路径上——如果以后发现锁竞争确实成为瓶颈，再按需引入专门
+/// 的并发 map 替换这里的实现，调用方的 API 不需要变。
+struct HotKeyTracker {
+    counts: Mutex<BTreeMap<Vec<u8>, u64>>,
+}
+
+impl HotKeyTracker {
+    fn new() -> Self {
+        Self { counts: Mutex::new(BTreeMap::new()) }
+    }
+
+    fn record(&self, key: &[u8]) {
+        let mut counts = self.counts.lock().expect("HotKeyTracker mutex poisoned");
+        *counts.entry(key.to_vec()).or_insert(0) += 1;
+    }
+
+    /// 返回访问次数最高的 `n` 个 key，按次数降序；次数相同的按 key 的
+    /// 字节序排列，保证结果稳定
+    fn top_n(&self, n: usize) -> Vec<(Vec<u8>, u64)> {
+        let counts = self.counts.lock().expect("HotKeyTracker mutex poisoned");
+        let mut entries: Vec<(Vec<u8>, u64)> = counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        entries.truncate(n);
+        entries
+    }
+}
+
+/// 包了一层访问统计的 [`Db`]，见 [`Db::with_hot_key_tracking`]
+pub struct TrackedDb {
+    db: Db,
+    tracker: HotKeyTracker,
+    top_n: usize,
+}
+
+impl TrackedDb {
+    /// 查找 key 对应的值，同时计入热点统计；其余语义见 [`Db::find`]
+    pub fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        self.tracker.record(key);
+        self.db.find(key, version)
+    }
+
+    /// 访问次数最高的 N 个 key（N 是 [`Db::with_hot_key_tracking`] 传入的值）
+    pub fn hot_keys_report(&self) -> Vec<(Vec<u8>, u64)> {
+        self.tracker.top_n(self.top_n)
+    }
+
+    /// 取回底层的 [`Db`]，丢弃已经收集的访问统计
+    pub fn into_inner(self) -> Db {
+        self.db
+    }
+
+    /// 把 [`TrackedDb::hot_keys_report`] 写成换行分隔的十六进制 key 文件，
+    /// 每行一个 key，按访问次数从高到低排列；返回写入的行数
+    ///
+    /// 条目数是 [`Db::with_hot_key_tracking`] 传入的 `top_n`，不是固定的
+    /// 1000——调用方如果想要"前 1000 个"，在包装时把 `top_n` 设成 1000
+    /// 即可，这里不重复编码这个假设。
+    pub fn dump_hot_keys_to_file(&self, path: impl AsRef<Path>) -> Result<usize, Error> {
+        let report = self.hot_keys_report();
+        let mut contents = String::new();
+        for (key, _count) in &report {
+            contents.push_str(&encode_hex_line(key));
+            contents.push('\n');
+        }
+        std::fs::write(path, contents).map_err(Error::Io)?;
+        Ok(report.len())
+    }
+}
+
+impl Db {
+    /// 包一层访问统计，返回的 [`TrackedDb::hot_keys_report`] 会给出访问
+    /// 次数最高的 `top_n` 个 key
+    pub fn with_hot_key_tracking(self, top_n: usize) -> TrackedDb {
+        TrackedDb { db: self, tracker: HotKeyTracker::new(), top_n }
+    }
+}
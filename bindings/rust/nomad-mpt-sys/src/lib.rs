@@ -3,7 +3,59 @@
 //! 提供 MonadDB MPT (Merkle Patricia Trie) 的 Rust FFI 绑定。
 
 pub mod async_fifo;
-pub use async_fifo::{AsyncFifo, FindResult, ResultStatus, LargeValue};
+pub use async_fifo::{
+    AsyncFifo, FindResult, ResultStatus, LargeValue, LargeValueStream, LargeValueBuffer,
+    LargeValueWatch, WaitTimeoutError, TraverseStream, TraverseError, HealthStatus, HealthWatch,
+    MultiVersionQuery, FifoStats, FindRequest, BatchError,
+};
+
+pub mod proof;
+pub use proof::{
+    Proof, ProofEncoder, ProofDecoder, ProofWithWitness, ExclusionProof, RlpError, NodeType,
+    TriePath, TriePathNode,
+};
+
+pub mod wal;
+pub use wal::{WalEntry, WalReader, WriteAheadLog};
+
+pub mod hot_keys;
+pub use hot_keys::TrackedDb;
+
+pub mod diff_stream;
+pub use diff_stream::{DiffIter, DiffStream};
+
+pub mod batch_ops;
+pub use batch_ops::{delete_updates_from_set, merge_updates, updates_from_btreemap, updates_from_hashmap};
+
+pub mod cached_db;
+pub use cached_db::{CacheStats, CachedDb};
+
+pub mod eth_state;
+pub use eth_state::{compute_ethereum_state_root, AccountState, Address, U256};
+
+pub mod fifo_metrics;
+pub use fifo_metrics::FifoMetricsCollector;
+
+pub mod bloom_filter;
+pub use bloom_filter::{BloomFilter, BloomFilterError};
+
+pub mod compactor;
+pub use compactor::{BackgroundCompactor, CompactorHandle};
+
+pub mod version_proof;
+pub use version_proof::{VersionProof, VersionProofIter};
+
+#[cfg(feature = "error-stack")]
+pub mod error_context;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(target_arch = "wasm32")]
+pub mod wasm_compat;
+
+#[cfg(feature = "serde")]
+use proof::{from_hex, to_hex};
 
 #[cxx::bridge(namespace = "monad::ffi")]
 pub mod ffi {
@@ -11,11 +63,12 @@ pub mod ffi {
     // 共享类型 (Rust ↔ C++)
     // ============================================================
     
-    /// 原始更新数据，用于跨 FFI 边界传递
-    /// 
-    /// 这是一个扁平结构，C++ 侧会将其转换为 UpdateList
+    /// 一组只读的 key/value 指针，跨 FFI 边界传递
+    ///
+    /// 从 `RawUpdate` 中拆出来单独定义，以便批量只读操作（如规划中的
+    /// `db_find_batch`）和遍历结果也能复用同一套字段布局，而不必重复定义。
     #[derive(Debug, Clone)]
-    struct RawUpdate {
+    struct RawKeyValue {
         /// Key 数据指针
         key_ptr: *const u8,
         /// Key 长度（字节）
@@ -24,6 +77,15 @@ pub mod ffi {
         value_ptr: *const u8,
         /// Value 长度（字节）
         value_len: usize,
+    }
+
+    /// 原始更新数据，用于跨 FFI 边界传递
+    ///
+    /// 这是一个扁平结构，C++ 侧会将其转换为 UpdateList
+    #[derive(Debug, Clone)]
+    struct RawUpdate {
+        /// Key/Value 指针
+        kv: RawKeyValue,
         /// 版本号
         version: i64,
         /// 嵌套更新指针（用于存储 trie）
@@ -31,7 +93,25 @@ pub mod ffi {
         /// 嵌套更新数量
         nested_len: usize,
     }
-    
+
+    /// 一组只读的 key 指针，跨 FFI 边界传递——和 [`RawKeyValue`] 一样的指针
+    /// 布局，但没有 value 那两个字段，用于 [`super::Db::contains_keys`]
+    /// 这种只需要 key、不涉及 value 的批量只读操作
+    #[derive(Debug, Clone)]
+    struct RawKey {
+        /// Key 数据指针
+        key_ptr: *const u8,
+        /// Key 长度（字节）
+        key_len: usize,
+    }
+
+    /// 一条自由形式的 key/value 调优选项，见 [`super::DbConfig::with_raw_option`]
+    #[derive(Debug, Clone)]
+    struct RawOption {
+        key: String,
+        value: String,
+    }
+
     // ============================================================
     // Opaque C++ Types
     // ============================================================
@@ -58,8 +138,40 @@ pub mod ffi {
             create: bool,
             history_length: u64,
         ) -> Result<UniquePtr<DbHandle>>;
-        
-        
+
+        /// 打开磁盘数据库（读写模式），WAL 路径单独指定
+        fn db_open_disk_rw_with_wal(
+            db_path: &str,
+            wal_path: &str,
+            create: bool,
+            history_length: u64,
+        ) -> Result<UniquePtr<DbHandle>>;
+
+        /// 打开磁盘数据库（读写模式），附带一组自由形式的调优选项，以及
+        /// `OnDiskDbConfig` 里两个真实存在、但默认开库逻辑没有暴露的字段：
+        /// `uring_entries`（见 [`super::DbConfig::with_io_ring_depth`]）和
+        /// `sq_thread_cpu`（见 [`super::DbConfig::with_io_threads`]）
+        ///
+        /// `opts` 这组 key/value 只是原样记在 binding 层，并不会真的改变
+        /// 引擎行为，见 [`super::DbConfig::with_raw_option`] 的文档；
+        /// `uring_entries`/`sq_thread_cpu` 不一样，它们是真的会传给
+        /// `mpt::OnDiskDbConfig`。`uring_entries == 0` 表示用引擎默认值
+        /// （512），`sq_thread_cpu < 0` 表示不开 `IORING_SETUP_SQPOLL`。
+        unsafe fn db_open_disk_rw_with_opts(
+            db_path: &str,
+            create: bool,
+            history_length: u64,
+            opts: *const RawOption,
+            opts_len: usize,
+            uring_entries: u32,
+            sq_thread_cpu: i64,
+        ) -> Result<UniquePtr<DbHandle>>;
+
+        /// 获取当前生效的选项集合（即通过 `db_open_disk_rw_with_opts` 设置
+        /// 的那些，原样回显）
+        fn db_get_effective_options(db: &DbHandle) -> Vec<RawOption>;
+
+
         /// 关闭数据库
         fn db_close(db: UniquePtr<DbHandle>);
         
@@ -76,7 +188,41 @@ pub mod ffi {
             key: &[u8],
             version: u64,
         ) -> Result<UniquePtr<NodeHandle>>;
-        
+
+        /// 检查 key 是否存在，不构造/拷贝值
+        fn db_contains_key(
+            db: &DbHandle,
+            key: &[u8],
+            version: u64,
+        ) -> bool;
+
+        /// [`db_contains_key`] 的批量版本：一次 FFI 调用对 `keys` 里的每个
+        /// key 各查一次，结果按顺序写进 `results`（长度必须等于
+        /// `count`）——省掉 N 次 `db_contains_key` 往返之间的调用开销
+        unsafe fn db_batch_contains_keys(
+            db: &DbHandle,
+            keys: *const RawKey,
+            count: usize,
+            version: u64,
+            results: *mut bool,
+        );
+
+        // ============================================================
+        // 并发读取限制
+        // ============================================================
+
+        /// 当前正在进行中的 find 调用数
+        fn db_get_concurrent_readers(db: &DbHandle) -> u32;
+
+        /// 设置并发 find 的上限，0 表示不限制
+        fn db_set_max_concurrent_readers(db: Pin<&mut DbHandle>, max: u32);
+
+        /// 尝试占用一个 reader 名额，超过上限时返回 false
+        fn db_try_acquire_reader(db: &DbHandle) -> bool;
+
+        /// 释放一个 reader 名额，必须与一次成功的 `db_try_acquire_reader` 配对
+        fn db_release_reader(db: &DbHandle);
+
         /// 批量更新
         /// 
         /// - root: 当前根节点（可以为空，表示从空树开始）
@@ -91,7 +237,22 @@ pub mod ffi {
             updates_len: usize,
             version: u64,
         ) -> Result<UniquePtr<NodeHandle>>;
-        
+
+        /// 条件更新：只有 condition_key 在 root 上的当前值匹配期望值时才执行
+        /// updates（乐观并发控制）；condition_met 写回条件是否满足
+        unsafe fn db_upsert_conditional(
+            db: Pin<&mut DbHandle>,
+            root: *const NodeHandle,
+            condition_key: &[u8],
+            expected_value_ptr: *const u8,
+            expected_value_len: usize,
+            expect_present: bool,
+            updates: *const RawUpdate,
+            updates_len: usize,
+            version: u64,
+            condition_met: &mut bool,
+        ) -> Result<UniquePtr<NodeHandle>>;
+
         // ============================================================
         // 元数据
         // ============================================================
@@ -129,14 +290,89 @@ pub mod ffi {
         
         /// 检查版本是否有效
         fn db_version_is_valid(db: &DbHandle, version: u64) -> bool;
-        
+
+        // ============================================================
+        // 原子 Root 替换
+        // ============================================================
+
+        /// 把 `new_root` 整根拷贝为 `version` 的根
+        fn db_swap_root(
+            db: Pin<&mut DbHandle>,
+            new_root: &NodeHandle,
+            version: u64,
+        ) -> Result<UniquePtr<NodeHandle>>;
+
         // ============================================================
         // Node 操作
         // ============================================================
         
         /// 克隆节点
         fn node_clone(node: &NodeHandle) -> UniquePtr<NodeHandle>;
-        
+
+        /// 分配一个空的、未关联任何节点的 NodeHandle，配合 `NodeHandlePool` 复用
+        fn node_alloc() -> Result<UniquePtr<NodeHandle>>;
+
+        /// 把一个 NodeHandle 原地重置为空状态
+        fn node_reset(node: Pin<&mut NodeHandle>);
+
+        /// 查找 key 对应的节点，写入调用方已分配好的 dst，避免每次 find 都
+        /// 触发一次 C++ 堆分配
+        fn db_find_into(
+            db: &DbHandle,
+            key: &[u8],
+            version: u64,
+            dst: Pin<&mut NodeHandle>,
+        ) -> Result<()>;
+
+        /// 合并两个独立的 trie 根，生成新的根节点
+        ///
+        /// `version_a`/`version_b` 是 `root_a`/`root_b` 各自实际所属的
+        /// 版本，`version` 是合并结果写入的目标版本号——三者互相独立。
+        ///
+        /// conflict_policy: 0=TakeA, 1=TakeB, 2=Error
+        unsafe fn db_merge_roots(
+            db: Pin<&mut DbHandle>,
+            root_a: *const NodeHandle,
+            version_a: u64,
+            root_b: *const NodeHandle,
+            version_b: u64,
+            version: u64,
+            conflict_policy: u8,
+        ) -> Result<UniquePtr<NodeHandle>>;
+
+        /// 把 `node` 下的所有 key/value 实际搬进 `db` 的节点存储，返回新建
+        /// 的根节点；见 `Db::copy_node` 的文档
+        fn db_adopt_node(
+            db: Pin<&mut DbHandle>,
+            node: &NodeHandle,
+            version: u64,
+        ) -> Result<UniquePtr<NodeHandle>>;
+
+        /// 在一个显式持有的根节点下查找 key，不依赖引擎按 version 记录的根
+        ///
+        /// 适用于 `db_merge_roots` 等返回"未注册"根的场景；未命中时返回空节点
+        unsafe fn db_find_in_root(
+            db: &DbHandle,
+            root: *const NodeHandle,
+            key: &[u8],
+            version: u64,
+        ) -> Result<UniquePtr<NodeHandle>>;
+
+        /// 数 `root` 下有值的节点（叶子）数量
+        fn db_count_leaves(
+            db: Pin<&mut DbHandle>,
+            root: &NodeHandle,
+            version: u64,
+        ) -> Result<u64>;
+
+        /// 和 `db_count_leaves` 一样，多一个 `thread_count` 参数表达并行意图
+        fn db_count_nodes_parallel(
+            db: Pin<&mut DbHandle>,
+            root: &NodeHandle,
+            version: u64,
+            thread_count: u32,
+        ) -> Result<u64>;
+
         /// 节点是否有值
         fn node_has_value(node: &NodeHandle) -> bool;
         
@@ -151,10 +387,32 @@ pub mod ffi {
         
         /// 复制 Merkle 数据到缓冲区
         fn node_copy_data(node: &NodeHandle, out: &mut [u8]) -> usize;
-        
+
+        /// 获取 Merkle 数据的只读指针，不拷贝；见 [`Node::with_data`]
+        fn node_data_ptr(node: &NodeHandle) -> *const u8;
+
+        /// 节点自身压缩边的 nibble 数（不是根到该节点的完整路径长度）
+        fn node_nibble_path_len(node: &NodeHandle) -> usize;
+
+        /// 把节点自身的 nibble 路径按字节打包写入 out，返回写入的字节数
+        fn node_copy_nibble_path(node: &NodeHandle, out: &mut [u8]) -> usize;
+
         /// 计算节点的 Merkle 根哈希（32 字节 Keccak256）
         fn node_compute_root_hash(node: &NodeHandle, out: &mut [u8]) -> usize;
-        
+
+        /// 交叉检查节点内部的值和 Merkle 数据是否一致，见 [`Node::verify_consistency`]
+        fn node_verify_consistency(node: &NodeHandle) -> bool;
+
+        // ============================================================
+        // Node RLP 序列化
+        // ============================================================
+
+        /// 将节点导出为 RLP 编码
+        fn node_to_rlp(node: &NodeHandle, out: &mut [u8]) -> usize;
+
+        /// 从 RLP 字节重建一个与数据库分离的节点
+        fn node_from_rlp_alloc(data: &[u8]) -> Result<UniquePtr<NodeHandle>>;
+
         // ============================================================
         // 性能优化
         // ============================================================
@@ -164,7 +422,34 @@ pub mod ffi {
         
         /// 检查数据库是否只读
         fn db_is_read_only(db: &DbHandle) -> bool;
-        
+
+        /// 释放分配器持有的、当前未使用的内存（glibc malloc_trim 或等价
+        /// 操作），返回近似释放的字节数
+        fn db_shrink_memory(db: &DbHandle) -> u64;
+
+        // ============================================================
+        // Merkle Proof
+        // ============================================================
+
+        /// 生成 key 在指定版本下的 Merkle 证明
+        ///
+        /// 返回值是多个证明节点拼接而成的缓冲区，每个节点以 4 字节小端长度前缀开头，
+        /// 详见 `proof::decode_raw_nodes`
+        fn db_get_proof_raw(db: &DbHandle, key: &[u8], version: u64) -> Result<Vec<u8>>;
+
+        // ============================================================
+        // 日志
+        // ============================================================
+
+        /// 设置 C++ 侧（quill）日志级别，低于该级别的日志会被静默
+        fn db_set_log_level(level: u8);
+
+        /// 注册日志回调；C++ 侧只保存这个指针，不会把 quill 的日志转发给它
+        ///
+        /// # Safety
+        /// `callback` 必须在整个进程生命周期内保持有效（通常是一个静态函数）
+        unsafe fn db_set_log_callback(callback: unsafe extern "C" fn(u8, *const i8, usize));
+
         /// 获取数据库统计信息
         unsafe fn db_get_stats(
             db: &DbHandle,
@@ -184,6 +469,81 @@ pub mod ffi {
 
 use cxx::UniquePtr;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 高层 API 的统一错误类型
+///
+/// FFI 层抛出的异常会被透明地包装为 `Error::Ffi`；其余变体用于在到达
+/// C++ 之前就能判断出的失败情况（例如版本校验）。
+#[derive(Debug)]
+pub enum Error {
+    /// 来自 C++ 侧的异常
+    Ffi(cxx::Exception),
+    /// 请求的版本在当前 DB 上不存在
+    InvalidVersion(u64),
+    /// 并发 `find` 数已达到 `DbConfig::with_max_concurrent_readers` 设置的上限
+    ConcurrencyLimitExceeded,
+    /// [`Db::upsert_conditional`] 的条件检查未通过：`root` 上 condition key
+    /// 当前的值与调用方期望的值不一致
+    ConditionFailed,
+    /// 请求的功能在当前（trimmed）引擎上没有底层支持，见具体调用点的文档
+    Unsupported(&'static str),
+    /// [`DbStats::assert_monotone`]：比较的两份快照里 `latest_version`
+    /// 反而变小了，说明两次采样之间发生了 rewind，或者参数传反了
+    NotMonotonic { older: u64, current: u64 },
+    /// [`Db::dump_trie_to_graphviz`] 写入调用方提供的 `writer` 时失败
+    Io(std::io::Error),
+    /// [`DbConfig::with_schema_version`]：打开磁盘 DB 时指定的 schema_version
+    /// 和磁盘上已经记录的 schema_version 不一致
+    SchemaMismatch { expected: u32, found: u32 },
+    /// [`Db::upsert_and_finalize`]：`upsert_with_root` 已经成功写入 `version`，
+    /// 但随后的 `update_finalized_version` 失败了；已经尝试过
+    /// `rewind_to_version` 把刚写的 `version` 撤销，`cause` 是原始错误
+    PartialWrite { cause: Box<Error> },
+    /// [`DbConfig::validate_strict`]：配置本身有问题，根本没有走到 FFI
+    /// 调用那一步；内容是 [`ConfigError`] 列表拼接后的描述
+    ConfigError(String),
+    /// [`Db::assert_equal_at_version`]：两边状态确实不一致，附带具体差在
+    /// 哪些 key 上的 [`InequalityReport`]
+    Unequal(InequalityReport),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Ffi(e) => write!(f, "ffi error: {e}"),
+            Error::InvalidVersion(v) => write!(f, "invalid version: {v}"),
+            Error::ConcurrencyLimitExceeded => write!(f, "concurrent reader limit exceeded"),
+            Error::ConditionFailed => write!(f, "upsert_conditional: condition key did not match expected value"),
+            Error::Unsupported(msg) => write!(f, "unsupported: {msg}"),
+            Error::NotMonotonic { older, current } => write!(
+                f,
+                "stats regressed: latest_version went from {older} to {current}"
+            ),
+            Error::Io(e) => write!(f, "io error: {e}"),
+            Error::SchemaMismatch { expected, found } => write!(
+                f,
+                "schema version mismatch: expected {expected}, found {found} on disk"
+            ),
+            Error::PartialWrite { cause } => write!(
+                f,
+                "upsert_and_finalize: write succeeded but finalize failed, rewound: {cause}"
+            ),
+            Error::ConfigError(msg) => write!(f, "invalid config: {msg}"),
+            Error::Unequal(report) => write!(f, "{report}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<cxx::Exception> for Error {
+    fn from(e: cxx::Exception) -> Self {
+        Error::Ffi(e)
+    }
+}
 
 /// 单个更新操作
 #[derive(Debug, Clone)]
@@ -220,14 +580,24 @@ impl<'a> Update<'a> {
         self.nested = nested;
         self
     }
+
+    /// 把一条 [`DiffEntry`] 转换成对应的 `Update`（`None` value 视为删除）
+    fn from_diff_entry(entry: &DiffEntry<'a>) -> Self {
+        match entry.value {
+            Some(value) => Self::put(entry.key, value),
+            None => Self::delete(entry.key),
+        }
+    }
     
     /// 转换为 RawUpdate
     fn to_raw(&self, version: i64, nested_raw: &[ffi::RawUpdate]) -> ffi::RawUpdate {
         ffi::RawUpdate {
-            key_ptr: self.key.as_ptr(),
-            key_len: self.key.len(),
-            value_ptr: self.value.map_or(ptr::null(), |v| v.as_ptr()),
-            value_len: self.value.map_or(0, |v| v.len()),
+            kv: ffi::RawKeyValue {
+                key_ptr: self.key.as_ptr(),
+                key_len: self.key.len(),
+                value_ptr: self.value.map_or(ptr::null(), |v| v.as_ptr()),
+                value_len: self.value.map_or(0, |v| v.len()),
+            },
             version,
             nested_ptr: if nested_raw.is_empty() { ptr::null() } else { nested_raw.as_ptr() },
             nested_len: nested_raw.len(),
@@ -235,6 +605,280 @@ impl<'a> Update<'a> {
     }
 }
 
+/// [`Update`] 的无生命周期版本，字段用 `Arc<[u8]>` 而不是借用，可以跨
+/// `.await` point 存活、塞进 channel 或者放进 `'static` 容器里
+#[derive(Debug, Clone)]
+pub struct OwnedUpdate {
+    pub key: Arc<[u8]>,
+    pub value: Option<Arc<[u8]>>,
+    pub nested: Vec<OwnedUpdate>,
+}
+
+impl OwnedUpdate {
+    /// 创建插入/更新操作
+    pub fn put(key: impl Into<Arc<[u8]>>, value: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            key: key.into(),
+            value: Some(value.into()),
+            nested: Vec::new(),
+        }
+    }
+
+    /// 创建删除操作
+    pub fn delete(key: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            key: key.into(),
+            value: None,
+            nested: Vec::new(),
+        }
+    }
+
+    /// 添加嵌套更新（用于账户存储 trie）
+    pub fn with_nested(mut self, nested: Vec<OwnedUpdate>) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// 借出一个和 `self` 活得一样长的 [`Update`]，用于复用现有的
+    /// `upsert`/`to_raw` 代码路径
+    fn as_borrowed(&self) -> Update<'_> {
+        Update {
+            key: &self.key,
+            value: self.value.as_deref(),
+            nested: self.nested.iter().map(OwnedUpdate::as_borrowed).collect(),
+        }
+    }
+}
+
+impl<'a> From<Update<'a>> for OwnedUpdate {
+    fn from(update: Update<'a>) -> Self {
+        Self {
+            key: Arc::from(update.key),
+            value: update.value.map(Arc::from),
+            nested: update.nested.into_iter().map(OwnedUpdate::from).collect(),
+        }
+    }
+}
+
+/// [`Db::write_batch`] 返回的构建器：先攒一批 `put`/`delete`，最后一次性
+/// `commit` 成一次 [`Db::upsert_with_root`] 调用
+///
+/// 和 [`Update::with_nested`] 那种消费 `self` 再返回的构建方式不一样，这里
+/// 每个方法都接收并返回 `&mut Self`，方便在调用处链式 `.put(..).put(..)`
+/// 之后再单独保留 batch 变量去 `commit`（`commit` 本身才消费 `self`）。
+pub struct WriteBatch<'a> {
+    updates: Vec<Update<'a>>,
+    version: u64,
+    root: Option<Node>,
+}
+
+impl<'a> WriteBatch<'a> {
+    /// 追加一次插入/更新
+    pub fn put(&mut self, key: &'a [u8], value: &'a [u8]) -> &mut Self {
+        self.updates.push(Update::put(key, value));
+        self
+    }
+
+    /// 追加一次删除
+    pub fn delete(&mut self, key: &'a [u8]) -> &mut Self {
+        self.updates.push(Update::delete(key));
+        self
+    }
+
+    /// 给刚刚 `put` 进去的账户 update 挂上嵌套存储更新（见
+    /// [`Update::with_nested`]）；`account` 应该和上一次 `put`/`delete` 用的
+    /// key 一致，调试模式下会校验这一点
+    pub fn with_nested(&mut self, account: &'a [u8], storage: Vec<Update<'a>>) -> &mut Self {
+        if let Some(last) = self.updates.last_mut() {
+            debug_assert_eq!(last.key, account, "with_nested 的 account 应该匹配上一次 put/delete 的 key");
+            last.nested = storage;
+        }
+        self
+    }
+
+    /// 把攒好的更新一次性提交给 `db`
+    ///
+    /// 空 batch（没有调用过任何 `put`/`delete`）直接返回当前根——有 `root`
+    /// 就原样返回，没有就走一次空更新列表的 `upsert_with_root` 拿到空树的根
+    /// （和 [`Db::delete_prefix`] 处理"扫不到匹配 key"时的空更新情形是同一个
+    /// 套路）。
+    pub fn commit(self, db: &mut Db) -> Result<Node, Error> {
+        if self.updates.is_empty() {
+            return match self.root {
+                Some(root) => Ok(root),
+                None => Ok(db.upsert_with_root(None, &[], self.version)?),
+            };
+        }
+        Ok(db.upsert_with_root(self.root.as_ref(), &self.updates, self.version)?)
+    }
+}
+
+/// [`Db::migrate_schema`] 用的单条迁移规则：把旧编码下的 key/value 转换成
+/// 新编码
+pub trait Migration {
+    /// 这次迁移要迁到的 schema 版本号（纯信息性字段，`migrate_schema` 本身
+    /// 不会拿它跟 `to_db_version` 做校验，调用方可以自己在外面比对）
+    fn version(&self) -> u32;
+
+    /// 把旧 key 转换成新 key；返回 `None` 表示这条记录应该被整体丢弃
+    fn migrate_key(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// 把旧 value 转换成新 value；返回 `None` 表示这条记录的值应该被丢弃
+    /// （等价于删除）
+    fn migrate_value(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// [`Db::open_with_hook`] 挂的写入中间件：每次 [`Db::upsert_with_hook`]
+/// 都会在真正写入之前/之后各调用一次
+pub trait UpsertHook {
+    /// 写入之前调用；返回 `Err` 会中止这次写入——[`Db::upsert_with_hook`]
+    /// 把这个错误原样返回，不会调用 FFI
+    fn pre_upsert(&self, updates: &[Update], version: u64) -> Result<(), Error>;
+
+    /// 写入成功之后调用，`root` 是刚写入产生的新根
+    fn post_upsert(&self, root: &Node, version: u64);
+}
+
+/// 一条已经计算好的差异记录，供 [`Db::apply_diff`] 使用
+///
+/// 注意：这个裁剪后的代码树里并没有对应的 `Db::diff`/`db_diff`（即计算两个
+/// 版本之间差异的那一侧），所以这里只提供"应用"侧：调用方自己算出
+/// `DiffEntry` 列表（例如逐 key 比较两个版本的 `find` 结果），`apply_diff`
+/// 负责把它们转成 `Update` 并 upsert 到目标 DB。
+#[derive(Debug, Clone)]
+pub struct DiffEntry<'a> {
+    /// Key
+    pub key: &'a [u8],
+    /// 目标版本中的值（`None` 表示该 key 在目标版本中被删除）
+    pub value: Option<&'a [u8]>,
+}
+
+/// [`Db::assert_equal_at_version`] 在两棵树不相等时返回的差异报告
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InequalityReport {
+    /// 只在 `a` 里出现的 key
+    pub keys_only_in_a: Vec<Vec<u8>>,
+    /// 只在 `b` 里出现的 key
+    pub keys_only_in_b: Vec<Vec<u8>>,
+    /// 两边都有，但值不一样的 key，`(key, a 的值, b 的值)`
+    pub keys_with_different_values: Vec<(Vec<u8>, Vec<u8>, Vec<u8>)>,
+}
+
+impl std::fmt::Display for InequalityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "tries differ: {} key(s) only in a, {} key(s) only in b, {} key(s) with different values",
+            self.keys_only_in_a.len(),
+            self.keys_only_in_b.len(),
+            self.keys_with_different_values.len(),
+        )?;
+        for key in &self.keys_only_in_a {
+            writeln!(f, "  only in a: {:02x?}", key)?;
+        }
+        for key in &self.keys_only_in_b {
+            writeln!(f, "  only in b: {:02x?}", key)?;
+        }
+        for (key, a_value, b_value) in &self.keys_with_different_values {
+            writeln!(f, "  differs:   {key:02x?}: a={a_value:02x?} b={b_value:02x?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InequalityReport {}
+
+/// C++ 侧（quill）日志级别
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+/// `Db::merge_roots` 遇到同一个 key 在两棵树中取值不同时的处理策略
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// 保留 `a` 中的值
+    TakeA = 0,
+    /// 保留 `b` 中的值
+    TakeB = 1,
+    /// 冲突时在 C++ 侧抛出异常，表现为 `Error::Ffi`
+    Error = 2,
+}
+
+/// 静态加密 key 的包装类型
+///
+/// 唯一存在的理由是覆盖 `Debug`：`DbConfig` 本身 derive 了 `Debug`，如果
+/// 直接存 `[u8; 32]`，打印配置（例如记日志）时密钥就会原样出现在输出里。
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("EncryptionKey").field(&"<redacted>").finish()
+    }
+}
+
+/// [`DbConfig::validate`] 发现的单个配置问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// 某个仅磁盘模式有效的选项被设置了，但 `path` 是 `None`（内存模式）；
+    /// 字符串是出问题的选项名
+    PathRequired(&'static str),
+    /// 两个选项的组合目前的 FFI 层无法同时表达，见各自字段的文档
+    ConflictingOptions(String),
+    /// 保留给未来可能出现的非法 `history_length` 取值。目前引擎接受任意
+    /// `u64`，这个 variant 永远不会被构造——和 [`HasherType::Blake3`]/
+    /// [`HasherType::Identity32`] 同样"保留但当前不产生效果"的处理方式一致
+    InvalidHistoryLength,
+    /// `path` 指向的位置不可写（见 [`DbConfig::validate`] 的检查逻辑）
+    PathNotWritable(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::PathRequired(option) => {
+                write!(f, "{option} requires disk mode (DbConfig::path must be Some)")
+            }
+            ConfigError::ConflictingOptions(msg) => write!(f, "conflicting options: {msg}"),
+            ConfigError::InvalidHistoryLength => write!(f, "invalid history_length"),
+            ConfigError::PathNotWritable(path) => write!(f, "path not writable: {path}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// [`DbConfig::validate`] 用到的文件系统可写性检查：`create == true` 时
+/// 检查父目录（`path` 本身这时候还不存在是正常的，留给 FFI 层真正创建），
+/// 否则检查 `path` 自己
+fn path_not_writable(path: &str, create: bool) -> Option<ConfigError> {
+    let check_path = if create {
+        match std::path::Path::new(path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => std::path::PathBuf::from("."),
+        }
+    } else {
+        std::path::PathBuf::from(path)
+    };
+
+    match std::fs::metadata(&check_path) {
+        Ok(metadata) if metadata.permissions().readonly() => {
+            Some(ConfigError::PathNotWritable(path.to_string()))
+        }
+        Ok(_) => None,
+        Err(_) if create => None,
+        Err(_) => Some(ConfigError::PathNotWritable(path.to_string())),
+    }
+}
+
 /// 数据库配置
 #[derive(Debug, Clone, Default)]
 pub struct DbConfig {
@@ -246,6 +890,75 @@ pub struct DbConfig {
     pub history_length: u64,
     /// 只读模式
     pub read_only: bool,
+    /// C++ 侧（quill）日志级别，`None` 表示不改变默认级别
+    pub log_level: Option<LogLevel>,
+    /// WAL 路径（仅磁盘模式有效），`None` 表示不单独指定
+    ///
+    /// 底层引擎没有传统意义上的独立 WAL 文件，这个路径会作为第二个存储
+    /// 分片传给引擎，效果等价于把部分数据放在另一个（通常更快的）设备上。
+    pub wal_path: Option<String>,
+    /// 是否启用基于内容寻址的 value 去重存储（见 [`Db::memory_usage`]）
+    pub value_dedup: bool,
+    /// 静态加密 key（见 [`DbConfig::with_encryption_key`]），`None` 表示不加密
+    pub encryption_key: Option<EncryptionKey>,
+    /// 并发 `find` 调用数上限（见 [`DbConfig::with_max_concurrent_readers`]），
+    /// `None` 表示不限制
+    pub max_concurrent_readers: Option<u32>,
+    /// 后台内存回收的触发间隔（见 [`DbConfig::with_memory_trim_interval`]），
+    /// `None` 表示不启用后台回收线程
+    pub memory_trim_interval: Option<Duration>,
+    /// 自由形式的调优选项（见 [`DbConfig::with_raw_option`]），仅磁盘模式有效
+    pub raw_options: std::collections::HashMap<String, String>,
+    /// 打开磁盘 DB 后自动调用一次 [`Db::point_in_time_restore`]（见
+    /// [`DbConfig::with_auto_restore`]），仅磁盘模式有效
+    pub auto_restore: bool,
+    /// 应用层 schema 版本号（见 [`DbConfig::with_schema_version`]），`None`
+    /// 表示不做校验
+    pub schema_version: Option<u32>,
+    /// 节点哈希算法（见 [`DbConfig::with_hasher`]），默认 `Keccak256`
+    pub hasher: HasherType,
+    /// 旁路 bloom filter 索引文件路径（见 [`DbConfig::with_bloom_index_path`]），
+    /// `None` 表示不启用
+    pub bloom_index_path: Option<String>,
+    /// `io_uring` submission/completion queue 深度（见
+    /// [`DbConfig::with_io_ring_depth`]），`None` 表示用引擎默认值
+    pub io_ring_depth: Option<u32>,
+    /// `IORING_SETUP_SQPOLL` 内核轮询线程（见 [`DbConfig::with_io_threads`]），
+    /// `None` 表示不开
+    pub io_threads: Option<u32>,
+    /// 内存模式 value 总字节数的软上限（见
+    /// [`DbConfig::with_memory_compaction_threshold`]），`None` 表示不限制
+    pub memory_compaction_threshold: Option<usize>,
+    /// 内存模式最多保留的版本数（见 [`DbConfig::with_max_memory_versions`]），
+    /// `None` 表示不限制
+    pub max_memory_versions: Option<usize>,
+}
+
+/// [`DbConfig::with_hasher`] 可选的节点哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HasherType {
+    /// 引擎实际使用、也是唯一真正可用的算法
+    #[default]
+    Keccak256,
+    /// 请求里提到的更快的测试用哈希——见 [`DbConfig::with_hasher`] 的限制
+    Blake3,
+    /// 请求里提到的"把 key 的 nibble 异或进 32 字节"的占位哈希——同样见
+    /// [`DbConfig::with_hasher`] 的限制
+    Identity32,
+}
+
+/// [`Db::version_exists`] 返回的版本分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionStatus {
+    /// 有效，且已经被 [`Db::update_finalized_version`] 标记为 finalized
+    Finalized,
+    /// 有效，但还没被标记为 finalized
+    Accessible,
+    /// 曾经有效，但已经被 prune 掉了
+    Pruned,
+    /// 从来没有写过这个版本号（超过 `latest_version()`，或者落在有效区间
+    /// 里但引擎说它无效）
+    NeverWritten,
 }
 
 impl DbConfig {
@@ -253,7 +966,7 @@ impl DbConfig {
     pub fn memory() -> Self {
         Self::default()
     }
-    
+
     /// 创建磁盘模式配置
     pub fn disk(path: impl Into<String>) -> Self {
         Self {
@@ -261,9 +974,24 @@ impl DbConfig {
             create: true,
             history_length: 0,
             read_only: false,
+            log_level: None,
+            wal_path: None,
+            value_dedup: false,
+            encryption_key: None,
+            max_concurrent_readers: None,
+            memory_trim_interval: None,
+            raw_options: std::collections::HashMap::new(),
+            auto_restore: false,
+            schema_version: None,
+            hasher: HasherType::Keccak256,
+            bloom_index_path: None,
+            io_ring_depth: None,
+            io_threads: None,
+            memory_compaction_threshold: None,
+            max_memory_versions: None,
         }
     }
-    
+
     /// 设置是否创建新数据库
     pub fn with_create(mut self, create: bool) -> Self {
         self.create = create;
@@ -281,12 +1009,427 @@ impl DbConfig {
         self.read_only = read_only;
         self
     }
-}
 
-/// MonadDB 数据库
-pub struct Db {
-    inner: UniquePtr<ffi::DbHandle>,
-}
+    /// 设置 C++ 侧（quill）日志级别
+    ///
+    /// 低于该级别的日志会被 quill 直接过滤掉，不会产生回调开销。
+    pub fn with_log_level(mut self, level: LogLevel) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// 设置 WAL 路径（仅磁盘模式有效）
+    ///
+    /// 生产环境常把 WAL 放在更快的 NVMe 设备上，主数据库放在容量更大的磁盘上。
+    /// 见字段文档：底层引擎并没有独立的 WAL 概念，这其实是把该路径作为
+    /// 第二个存储分片传给引擎。
+    pub fn with_wal_path(mut self, path: impl Into<String>) -> Self {
+        self.wal_path = Some(path.into());
+        self
+    }
+
+    /// 启用基于内容寻址的 value 去重存储
+    ///
+    /// 许多版本之间未变化的 key 存的是同一份 value，启用后相同字节序列的
+    /// value 只在 Rust 侧的去重表中保留一份，trie 中只写入它的 keccak256
+    /// 哈希（32 字节）。`Db::find` 会透明地解引用这个哈希。
+    pub fn with_value_dedup(mut self, enabled: bool) -> Self {
+        self.value_dedup = enabled;
+        self
+    }
+
+    /// 启用静态加密：所有写入的 value 在落入 C++ 引擎前用 AES-256-GCM 加密
+    ///
+    /// C++ 侧完全不知道加密的存在，看到的只是不透明字节。`Db::find` 在读取
+    /// 时根据魔数前缀自动识别并解密。
+    pub fn with_encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(EncryptionKey(key));
+        self
+    }
+
+    /// 设置并发 `find` 调用数上限；超过上限的 `find` 会立即返回
+    /// `Err(Error::ConcurrencyLimitExceeded)`，而不是阻塞等待名额
+    pub fn with_max_concurrent_readers(mut self, max: u32) -> Self {
+        self.max_concurrent_readers = Some(max);
+        self
+    }
+
+    /// 启动一个后台线程，每隔 `interval` 调用一次 [`Db::shrink_memory`]
+    ///
+    /// 线程句柄保存在 `Db` 里，`Db` 被 drop 时会先通知线程退出再 join，
+    /// 见 [`Db::shrink_memory`]。
+    pub fn with_memory_trim_interval(mut self, interval: Duration) -> Self {
+        self.memory_trim_interval = Some(interval);
+        self
+    }
+
+    /// 设置一条自由形式的调优选项（例如 `"block_cache_size"`）
+    ///
+    /// 底层 `OnDiskDbConfig`（见 `depend/monad/category/mpt/ondisk_db_config.hpp`）
+    /// 目前没有通用的 tuning knob 入口，只有固定的几个具名字段（`rd_buffers`、
+    /// `file_size_db` 等，均已经有各自专属的 `DbConfig` 字段或硬编码默认值）。
+    /// 这里设置的 key/value 不会被引擎实际使用，只是原样记录下来，之后可以
+    /// 通过 [`Db::effective_options`] 读回来——主要用于让调用方和配置管理
+    /// 系统之间传递一些尚未对接到引擎的参数，等引擎侧支持了再逐个接上。
+    pub fn with_raw_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.raw_options.insert(key.into(), value.into());
+        self
+    }
+
+    /// 打开磁盘 DB 之后自动调用一次 [`Db::point_in_time_restore`]
+    ///
+    /// 进程崩溃重启之后用来清掉 `finalized_version` 之后可能存在部分写入的
+    /// 版本；见 `Db::point_in_time_restore` 文档里关于 `rewind_to_version`
+    /// 当前限制的说明——这个开关能触发那次 prune，但不保证
+    /// `latest_version()` 真的回退。
+    pub fn with_auto_restore(mut self, enabled: bool) -> Self {
+        self.auto_restore = enabled;
+        self
+    }
+
+    /// 设置应用层 schema 版本号，用于在不同版本之间做前向兼容性检查
+    ///
+    /// 不同的应用版本可能用不同的 key 编码约定；磁盘模式下 [`Db::open`] 会
+    /// 把这个值和磁盘上（上一次打开时）记录的 schema_version 比较，不一致
+    /// 就返回 `Err(Error::SchemaMismatch { .. })`，而不是静默地用新的编码
+    /// 约定去读旧数据。内存模式没有"上一次"可比较，这个值只是原样存进
+    /// [`Db::schema_version`]。
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
+
+    /// 设置节点哈希算法（默认 [`HasherType::Keccak256`]）
+    ///
+    /// # 当前限制
+    /// `mpt::Node` 的哈希计算（`depend/monad/category/mpt` 下遍历不到任何
+    /// pluggable hasher 的钩子）是在 C++ 引擎内部硬编码为 Keccak256 的，没有
+    /// 一个 `db_open_memory_with_hasher` 之类的入口能让它换成别的算法——
+    /// 这和 [`DbConfig::with_raw_option`] 文档里提到的"引擎没有通用调优
+    /// 入口"是同一类限制。所以这里没有伪造一个假装生效的 FFI 调用：
+    /// [`HasherType::Blake3`]/[`HasherType::Identity32`] 会让 [`Db::open`]
+    /// 直接返回 `Err(Error::Unsupported(..))`，不管是内存还是磁盘模式。
+    /// 如果单测只是想避开 Keccak256 的开销、不关心哈希是否和生产环境兼容，
+    /// [`crate::testing::MockDb`] 已经是这个仓库里现成的方案。
+    pub fn with_hasher(mut self, hasher: HasherType) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// 启用一个旁路 bloom filter 索引，持久化在 `path` 指向的文件里
+    ///
+    /// 每次 [`Db::open`] 会尝试从 `path` 反序列化一个已有的过滤器；文件
+    /// 不存在或内容损坏时，从空过滤器重新开始（对已经在文件里的 key 会
+    /// 漏报——这是重新建索引的代价，不是 bug）。之后每次 [`Db::upsert`]
+    /// 成功后，本次更新涉及的 key 会被插入过滤器，并整体重新写回 `path`。
+    ///
+    /// 过滤器按固定的 [`bloom_filter::DEFAULT_BLOOM_EXPECTED_ITEMS`] 条目数
+    /// 和 0.1% 误报率预先分配好位数组大小；如果实际插入的 key 数量远超这个
+    /// 预估值，实际误报率会比 0.1% 更高——这是标准 bloom filter 的固有限制
+    /// （见 [`bloom_filter::BloomFilter::new`] 的文档），这里没有做动态扩容。
+    pub fn with_bloom_index_path(mut self, path: impl Into<String>) -> Self {
+        self.bloom_index_path = Some(path.into());
+        self
+    }
+
+    /// 设置 `io_uring` submission/completion queue 深度（`OnDiskDbConfig::uring_entries`），
+    /// 仅磁盘模式有效
+    ///
+    /// 和 [`DbConfig::with_raw_option`] 设置的那些 key/value 不一样，这是
+    /// `OnDiskDbConfig` 上一个真实存在的字段——`n == 0` 等价于不调用这个
+    /// 方法，用引擎默认值（512）。
+    pub fn with_io_ring_depth(mut self, n: u32) -> Self {
+        self.io_ring_depth = Some(n);
+        self
+    }
+
+    /// 开启/关闭 `IORING_SETUP_SQPOLL` 内核轮询线程（`OnDiskDbConfig::sq_thread_cpu`），
+    /// 仅磁盘模式有效
+    ///
+    /// # 和请求字面意思的差异
+    /// 请求里把这个参数描述成"worker 线程池大小"，但 `OnDiskDbConfig` 真实
+    /// 暴露的是单个可选的 SQPOLL 内核线程，绑定到一个指定 CPU——不是一个
+    /// 可以配出 N 个线程的池子。这里把 `n` 按"0 = 不开，非 0 = 开，绑定到
+    /// CPU `n - 1`"解释，映射到这一个真实字段上，而不是虚构一个引擎不支持
+    /// 的线程池大小参数。开启 SQPOLL 通常需要 root 权限，调用方需要自己
+    /// 保证这一点，否则打开磁盘 DB 会失败。
+    pub fn with_io_threads(mut self, n: u32) -> Self {
+        self.io_threads = Some(n);
+        self
+    }
+
+    /// 设置 `io_uring` 单次提交的批量大小
+    ///
+    /// # 当前限制
+    /// `OnDiskDbConfig`（见 `depend/monad/category/mpt/ondisk_db_config.hpp`）
+    /// 没有任何字段对应"单次提交批量大小"这个概念（`rd_buffers`/`wr_buffers`
+    /// 是读写缓冲池大小，`concurrent_read_io_limit` 是并发 IO 上限，都不是
+    /// 这个）——这里按 [`DbConfig::with_raw_option`] 同样的方式处理：只是
+    /// 原样记一条 `"io_ring_batch_size"` 调优选项，供之后通过
+    /// [`Db::effective_options`] 读回来，并不会真的影响 io_uring 的提交行为。
+    pub fn with_io_ring_batch_size(self, n: u32) -> Self {
+        self.with_raw_option("io_ring_batch_size", n.to_string())
+    }
+
+    /// 设置内存模式 value 总字节数的软上限，超过时 [`Db::upsert_with_root`]
+    /// 会自动丢弃内存版本缓存里最旧的一半版本（见 [`Db::approximate_memory_bytes`]）
+    ///
+    /// # 当前限制
+    /// 底层引擎没有暴露 `db_compact`（请求里提到的压缩原语不存在，见
+    /// `depend/monad/category/mpt/db.hpp`），所以超过阈值时唯一能做的是
+    /// [`Db::prune_before_version`] 式的丢弃旧版本，不是真正意义上的
+    /// "压缩"。仅内存模式有效，磁盘模式下这个字段会被忽略。
+    pub fn with_memory_compaction_threshold(mut self, bytes: usize) -> Self {
+        self.memory_compaction_threshold = Some(bytes);
+        self
+    }
+
+    /// 限制内存模式最多保留的版本数，超过时自动丢弃最旧的版本，使它们之后
+    /// 的 [`Db::load_root`] 返回 `Err(Error::InvalidVersion)`
+    ///
+    /// 仅内存模式有效，磁盘模式下这个字段会被忽略（磁盘模式的版本历史由
+    /// `history_length` 管理，见 [`DbConfig::with_history_length`]）。
+    pub fn with_max_memory_versions(mut self, n: usize) -> Self {
+        self.max_memory_versions = Some(n);
+        self
+    }
+
+    /// 列出当前配置里所有能在打开之前发现的问题，而不是在 [`Db::open`]
+    /// 里逐个 panic 或者等到 FFI 调用失败才报错
+    ///
+    /// 目前检查的内容：
+    /// - `wal_path`/`raw_options`/`auto_restore`/`io_ring_depth`/
+    ///   `io_threads` 这些"仅磁盘模式有效"的选项在内存模式（`path ==
+    ///   None`）下被设置
+    /// - `wal_path` 和 `raw_options`/`io_ring_depth`/`io_threads` 同时设置：
+    ///   见 [`Db::open`] 的实现，底层 FFI 目前只能走其中一条分支，两者
+    ///   同时设置时 `wal_path` 会被默默忽略，所以这里显式拒绝而不是让它
+    ///   悄悄发生
+    /// - `create` 和 `read_only` 同时为 `true`——不可能创建一个只读数据库
+    /// - 磁盘模式下 `path` 指向的位置不可写
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+
+        if self.path.is_none() {
+            if self.wal_path.is_some() {
+                errors.push(ConfigError::PathRequired("wal_path"));
+            }
+            if !self.raw_options.is_empty() {
+                errors.push(ConfigError::PathRequired("raw_option"));
+            }
+            if self.auto_restore {
+                errors.push(ConfigError::PathRequired("auto_restore"));
+            }
+            if self.io_ring_depth.is_some() {
+                errors.push(ConfigError::PathRequired("io_ring_depth"));
+            }
+            if self.io_threads.is_some() {
+                errors.push(ConfigError::PathRequired("io_threads"));
+            }
+        } else if self.wal_path.is_some()
+            && (!self.raw_options.is_empty() || self.io_ring_depth.is_some() || self.io_threads.is_some())
+        {
+            errors.push(ConfigError::ConflictingOptions(
+                "wal_path 不能和 raw_options/io_ring_depth/io_threads 同时设置：\
+                 Db::open 目前只能走其中一条 FFI 分支打开磁盘数据库"
+                    .to_string(),
+            ));
+        }
+
+        if self.create && self.read_only {
+            errors.push(ConfigError::ConflictingOptions(
+                "create 和 read_only 不能同时为 true".to_string(),
+            ));
+        }
+
+        if let Some(path) = &self.path {
+            if !self.read_only {
+                if let Some(err) = path_not_writable(path, self.create) {
+                    errors.push(err);
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// [`DbConfig::validate`] 的"严格"版本：有问题直接返回
+    /// `Err(Error::ConfigError)`，而不是把 `Vec<ConfigError>` 交给调用方
+    /// 自己处理
+    pub fn validate_strict(&self) -> Result<(), Error> {
+        let errors = self.validate();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            let msg = errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+            Err(Error::ConfigError(msg))
+        }
+    }
+}
+
+// ============================================================
+// C++ 日志桥接到 tracing
+// ============================================================
+
+/// 通过 `db_set_log_callback` 注册给 C++ 侧的日志回调入口
+///
+/// C++ 侧只保存这个指针，不会调用它（见 `ffi::db_set_log_callback`）。
+///
+/// # Safety
+/// 被调用时，`msg` 必须指向 `msg_len` 字节的有效 UTF-8（或至少
+/// 是合法 ASCII 子集）缓冲区，且调用期间保持有效。
+unsafe extern "C" fn log_bridge_callback(level: u8, msg: *const i8, msg_len: usize) {
+    let bytes = std::slice::from_raw_parts(msg as *const u8, msg_len);
+    let text = String::from_utf8_lossy(bytes);
+
+    match level {
+        1 => tracing::event!(tracing::Level::ERROR, target: "nomad_mpt_sys::cpp", "{text}"),
+        2 => tracing::event!(tracing::Level::WARN, target: "nomad_mpt_sys::cpp", "{text}"),
+        3 => tracing::event!(tracing::Level::INFO, target: "nomad_mpt_sys::cpp", "{text}"),
+        4 => tracing::event!(tracing::Level::DEBUG, target: "nomad_mpt_sys::cpp", "{text}"),
+        _ => tracing::event!(tracing::Level::TRACE, target: "nomad_mpt_sys::cpp", "{text}"),
+    }
+}
+
+/// 把 [`log_bridge_callback`] 注册为 C++ 侧的日志回调
+///
+/// C++ 侧只保存这个回调指针，quill 的日志不会经由它转发到 `tracing`，
+/// 仍然只输出到 quill 自己的控制台 handler，`tracing-subscriber` 捕获不到。
+pub fn install_tracing_log_bridge() {
+    unsafe {
+        ffi::db_set_log_callback(log_bridge_callback);
+    }
+}
+
+/// 一次 prune 事件：这次触发回收掉的历史版本号列表，见 [`Db::subscribe_pruning`]
+#[derive(Debug, Clone)]
+pub struct PruningEvent {
+    pub pruned_versions: Vec<u64>,
+}
+
+/// [`Db::subscribe_pruning`] 返回的订阅句柄
+pub struct PruningSubscription {
+    receiver: tokio::sync::mpsc::Receiver<PruningEvent>,
+}
+
+impl PruningSubscription {
+    /// 等待下一次 prune 事件；对应的 [`Db`] 被 drop 之后返回 `None`
+    pub async fn recv(&mut self) -> Option<PruningEvent> {
+        self.receiver.recv().await
+    }
+}
+
+/// MonadDB 数据库
+pub struct Db {
+    inner: UniquePtr<ffi::DbHandle>,
+    /// 打开时指定的 WAL 路径，仅用于 `stats()` 回显；引擎本身不区分主存储和 WAL
+    wal_path: Option<String>,
+    /// 是否启用 value 去重（见 `DbConfig::with_value_dedup`）
+    value_dedup: bool,
+    /// 内容寻址的 value 去重表：keccak256(value) -> value
+    dedup_store: std::collections::HashMap<[u8; 32], Vec<u8>>,
+    /// 静态加密 key（见 `DbConfig::with_encryption_key`），`None` 表示不加密
+    encryption_key: Option<EncryptionKey>,
+    /// 每次 `upsert_with_root` 成功后被通知的句柄，见 [`Db::watch_version_advance`]
+    version_notify: Arc<tokio::sync::Notify>,
+    /// 后台内存回收线程（见 [`DbConfig::with_memory_trim_interval`]），
+    /// `None` 表示未启用
+    trim_thread: Option<TrimThread>,
+    /// [`Db::subscribe_pruning`] 注册的订阅者，见 [`Db::notify_pruned`]
+    pruning_senders: Vec<tokio::sync::mpsc::Sender<PruningEvent>>,
+    /// 缓存的历史根节点（`version` -> root），见 [`Db::load_root`]。内存
+    /// 模式下 [`Db::upsert_with_root`] 每次成功都会缓存；磁盘模式下默认
+    /// 为空（由引擎自己维护版本历史），只有 [`Db::swap_root`] 换上去的根
+    /// 会进这里（因为 `db_swap_root` 不让引擎记录它）
+    memory_roots: std::collections::HashMap<u64, Node>,
+    /// [`Db::pin_version`] 标记的、prune 时应该保留的版本集合，见
+    /// [`Db::prune_before_version`]
+    pinned_versions: std::collections::BTreeSet<u64>,
+    /// [`DbConfig::with_schema_version`] 指定的值，见 [`Db::schema_version`]；
+    /// 未指定时为 0
+    schema_version: u32,
+    /// 磁盘模式下打开时的 `db_path`，见 [`Db::estimated_disk_size`]；
+    /// 内存模式为 `None`
+    db_path: Option<String>,
+    /// [`DbConfig::with_bloom_index_path`] 启用的旁路索引，`None` 表示未启用
+    bloom_index: Option<BloomIndex>,
+    /// [`Db::open_with_hook`] 挂的写入中间件，`None` 表示未启用；见
+    /// [`Db::upsert_with_hook`] 的限制——目前只有这一个入口方法会触发它
+    hook: Option<Arc<dyn UpsertHook + Send + Sync>>,
+    /// [`DbConfig::with_memory_compaction_threshold`]，`None` 表示不限制
+    memory_compaction_threshold: Option<usize>,
+    /// [`DbConfig::with_max_memory_versions`]，`None` 表示不限制
+    max_memory_versions: Option<usize>,
+    /// [`Db::approximate_memory_bytes`] 的运行计数器，只在
+    /// [`Db::upsert_with_root`] 里随 `memory_roots` 的插入/丢弃增减，只在
+    /// 内存模式下维护（磁盘模式始终是 0）
+    approx_memory_bytes: usize,
+}
+
+/// [`Db::bloom_index`] 字段的内部状态：过滤器本身 + 落盘路径
+struct BloomIndex {
+    filter: BloomFilter,
+    path: String,
+}
+
+// Safety: `mpt::Db::find` 是 const 方法，引擎内部用 reader-writer lock 支持
+// 多线程并发只读访问（见 `DbConfig::with_max_concurrent_readers`）；需要独占
+// 访问的操作（`upsert` 等）都要求 `&mut self`，由 Rust 的借用检查保证互斥。
+unsafe impl Send for Db {}
+unsafe impl Sync for Db {}
+
+impl Drop for Db {
+    fn drop(&mut self) {
+        // 必须先停下并 join 后台 trim 线程，再让下面字段序自动生成的 drop
+        // glue 销毁 `inner`（UniquePtr<DbHandle>）——否则线程里缓存的裸指针
+        // 会在 DbHandle 析构之后变成悬垂指针。`TrimThread::drop` 负责
+        // 实际的停止信号 + join。
+        self.trim_thread.take();
+    }
+}
+
+/// [`DbConfig::with_memory_trim_interval`] 对应的后台回收线程
+///
+/// 只持有一个裸的 `*const DbHandle`，不是 `&'static`——`db_shrink_memory`
+/// 在 C++ 侧完全忽略这个参数（它触发的是进程级的 `malloc_trim`，不读写
+/// 任何 DB 状态），所以即使这个线程和持有 `&mut Db` 的线程同时运行，也不
+/// 会产生数据竞争；裸指针只是用来满足 FFI 签名。
+struct TrimThread {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TrimThread {
+    fn spawn(db: *const ffi::DbHandle, interval: Duration) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_for_thread = Arc::clone(&shutdown);
+
+        // Safety: 见上面 `TrimThread` 的文档注释——db_shrink_memory 不会
+        // 解引用超出"这是个合法指针"以外的任何内容
+        let handle = std::thread::spawn(move || {
+            while !shutdown_for_thread.load(Ordering::Relaxed) {
+                std::thread::sleep(interval);
+                if shutdown_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+                unsafe {
+                    ffi::db_shrink_memory(&*db);
+                }
+            }
+        });
+
+        Self { shutdown, handle: Some(handle) }
+    }
+}
+
+impl Drop for TrimThread {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
 
 impl Db {
     /// 使用配置打开数据库
@@ -298,7 +1441,26 @@ impl Db {
     /// # 未实现的功能
     /// - 只读磁盘模式 (`read_only: true`) 尚未实现，会返回错误
     /// - 清空数据库 (`db_clear`) 已移除，请使用 CLI 工具
-    pub fn open(config: DbConfig) -> Result<Self, cxx::Exception> {
+    ///
+    /// # schema_version
+    /// 磁盘模式下，如果 `config.schema_version` 是 `Some`，会和上一次打开
+    /// 时记录下来的版本号比较（见 [`DbConfig::with_schema_version`]），不一致
+    /// 就返回 `Err(Error::SchemaMismatch { .. })`。底层引擎（`OnDiskDbConfig`）
+    /// 没有专门的元数据槛位能存这个值（参见 [`DbConfig::with_raw_option`]
+    /// 文档），所以这里没有用虚构的 FFI 函数，而是在 DB 目录下维护一个
+    /// 独立的 `.schema_version` sidecar 文件，读写都是普通的 `std::fs` 调用。
+    pub fn open(config: DbConfig) -> Result<Self, Error> {
+        config.validate_strict()?;
+        if config.hasher != HasherType::Keccak256 {
+            // 见 `DbConfig::with_hasher` 的限制：引擎没有暴露切换哈希算法的接口
+            return Err(Error::Unsupported(
+                "Db::open: only HasherType::Keccak256 is backed by the underlying engine",
+            ));
+        }
+        if let Some(level) = config.log_level {
+            ffi::db_set_log_level(level as u8);
+        }
+
         let inner = match &config.path {
             None => {
                 // 内存模式
@@ -311,109 +1473,1383 @@ impl Db {
                     panic!("Read-only disk mode is not yet implemented (requires RODb support). \
                            Use read_only: false or open the database with standard tools.");
                 }
-                ffi::db_open_disk_rw(path, config.create, config.history_length)?
+                if !config.raw_options.is_empty() || config.io_ring_depth.is_some() || config.io_threads.is_some() {
+                    let opts: Vec<ffi::RawOption> = config.raw_options.iter()
+                        .map(|(key, value)| ffi::RawOption {
+                            key: key.clone(),
+                            value: value.clone(),
+                        })
+                        .collect();
+                    let uring_entries = config.io_ring_depth.unwrap_or(0);
+                    // 见 `DbConfig::with_io_threads` 的文档：0 表示不开 SQPOLL，
+                    // 非 0 表示开，绑定到 CPU `n - 1`
+                    let sq_thread_cpu: i64 = match config.io_threads {
+                        Some(0) | None => -1,
+                        Some(n) => (n - 1) as i64,
+                    };
+                    // Safety: `opts` 在这次调用期间一直存活，指针和长度匹配
+                    unsafe {
+                        ffi::db_open_disk_rw_with_opts(
+                            path,
+                            config.create,
+                            config.history_length,
+                            opts.as_ptr(),
+                            opts.len(),
+                            uring_entries,
+                            sq_thread_cpu,
+                        )?
+                    }
+                } else {
+                    match &config.wal_path {
+                        None => ffi::db_open_disk_rw(path, config.create, config.history_length)?,
+                        Some(wal_path) => ffi::db_open_disk_rw_with_wal(
+                            path,
+                            wal_path,
+                            config.create,
+                            config.history_length,
+                        )?,
+                    }
+                }
+            }
+        };
+        let mut inner = inner;
+        if let Some(max) = config.max_concurrent_readers {
+            ffi::db_set_max_concurrent_readers(inner.pin_mut(), max);
+        }
+
+        if config.auto_restore && config.path.is_some() {
+            // 见 `Db::point_in_time_restore` 的文档：逻辑和它完全一样，这里
+            // 不能直接调用它——它要求 `&mut self`，而这里还在构造 `Self`，
+            // 只能先拿 `inner` 自己操作底层 ffi。
+            let finalized = ffi::db_get_finalized_version(&inner);
+            let latest = ffi::db_get_latest_version(&inner);
+            if finalized != u64::MAX && finalized < latest {
+                ffi::db_rewind_to_version(inner.pin_mut(), finalized)?;
+            }
+        }
+
+        let schema_version = match &config.path {
+            Some(path) => {
+                let sidecar = std::path::Path::new(path).join(".schema_version");
+                let found = std::fs::read_to_string(&sidecar)
+                    .ok()
+                    .and_then(|s| s.trim().parse::<u32>().ok());
+                if let (Some(expected), Some(found)) = (config.schema_version, found) {
+                    if expected != found {
+                        return Err(Error::SchemaMismatch { expected, found });
+                    }
+                }
+                let version = config.schema_version.or(found).unwrap_or(0);
+                if config.schema_version.is_some() {
+                    std::fs::write(&sidecar, version.to_string()).map_err(Error::Io)?;
+                }
+                version
             }
+            None => config.schema_version.unwrap_or(0),
         };
-        Ok(Self { inner })
+
+        let trim_thread = config.memory_trim_interval.map(|interval| {
+            TrimThread::spawn(&*inner as *const ffi::DbHandle, interval)
+        });
+
+        let bloom_index = config.bloom_index_path.map(|path| {
+            let filter = std::fs::read(&path)
+                .ok()
+                .and_then(|bytes| bloom_filter::BloomFilter::deserialize(&bytes).ok())
+                .unwrap_or_else(|| {
+                    bloom_filter::BloomFilter::new(
+                        bloom_filter::DEFAULT_BLOOM_EXPECTED_ITEMS,
+                        bloom_filter::DEFAULT_BLOOM_FALSE_POSITIVE_RATE,
+                    )
+                });
+            BloomIndex { filter, path }
+        });
+
+        Ok(Self {
+            inner,
+            wal_path: config.wal_path,
+            value_dedup: config.value_dedup,
+            dedup_store: std::collections::HashMap::new(),
+            encryption_key: config.encryption_key,
+            version_notify: Arc::new(tokio::sync::Notify::new()),
+            trim_thread,
+            pruning_senders: Vec::new(),
+            memory_roots: std::collections::HashMap::new(),
+            pinned_versions: std::collections::BTreeSet::new(),
+            schema_version,
+            db_path: config.path,
+            bloom_index,
+            hook: None,
+            memory_compaction_threshold: config.memory_compaction_threshold,
+            max_memory_versions: config.max_memory_versions,
+            approx_memory_bytes: 0,
+        })
     }
-    
+
     /// 打开内存数据库
-    pub fn open_memory() -> Result<Self, cxx::Exception> {
+    pub fn open_memory() -> Result<Self, Error> {
         Self::open(DbConfig::memory())
     }
-    
+
+    /// 和 [`Db::open`] 一样打开数据库，但额外挂上一个写入前后都会被调用的
+    /// [`UpsertHook`]（审计日志、事件溯源之类的中间件场景）
+    ///
+    /// # 当前限制
+    /// hook 目前只会在调用 [`Db::upsert_with_hook`] 时被触发——`Db::upsert`/
+    /// `Db::upsert_with_root`/`Db::upsert_conditional` 等其它写入路径不会
+    /// 自动经过它。原因是 [`Db::upsert_with_root`] 的返回类型是裸的
+    /// `cxx::Exception`（让 FFI 异常原样冒泡），而 `pre_upsert` 失败产生的
+    /// 是 Rust 侧的 [`Error`]，两者没有转换关系，没办法让 `pre_upsert` 的
+    /// 失败从 `upsert_with_root` 内部冒出来；只能新增一个专门返回 `Error`
+    /// 的入口方法，而不是改掉 `upsert_with_root` 这个被广泛使用的签名。
+    pub fn open_with_hook(
+        config: DbConfig,
+        hook: Arc<dyn UpsertHook + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let mut db = Self::open(config)?;
+        db.hook = Some(hook);
+        Ok(db)
+    }
+
+    /// 经过 [`Db::open_with_hook`] 挂的 [`UpsertHook`] 的批量更新
+    ///
+    /// 见 [`Db::open_with_hook`] 的限制：这是目前唯一会触发 hook 的写入
+    /// 路径。没有挂 hook（`self.hook` 是 `None`）时，行为和直接调用
+    /// [`Db::upsert_with_root`] 完全一样。
+    pub fn upsert_with_hook(
+        &mut self,
+        root: Option<&Node>,
+        updates: &[Update],
+        version: u64,
+    ) -> Result<Node, Error> {
+        match self.hook.clone() {
+            Some(hook) => {
+                hook.pre_upsert(updates, version)?;
+                let node = self.upsert_with_root(root, updates, version)?;
+                hook.post_upsert(&node, version);
+                Ok(node)
+            }
+            None => Ok(self.upsert_with_root(root, updates, version)?),
+        }
+    }
+
+    /// 打开内存数据库，使用 `hasher` 而不是默认的 Keccak256
+    ///
+    /// 见 [`DbConfig::with_hasher`] 的限制：目前只有
+    /// [`HasherType::Keccak256`] 真正可用，传别的值会得到
+    /// `Err(Error::Unsupported(..))`。
+    pub fn open_memory_with_custom_hasher(hasher: HasherType) -> Result<Self, Error> {
+        Self::open(DbConfig::memory().with_hasher(hasher))
+    }
+
     /// 打开磁盘数据库（读写模式）
-    pub fn open_disk(path: impl Into<String>) -> Result<Self, cxx::Exception> {
+    pub fn open_disk(path: impl Into<String>) -> Result<Self, Error> {
         Self::open(DbConfig::disk(path))
     }
-    
+
+    /// 打开磁盘数据库（读写模式），附带一组 [`DbConfig::with_raw_option`]
+    /// 调优选项
+    pub fn open_disk_rw_with_options(
+        path: impl Into<String>,
+        options: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, Error> {
+        let mut config = DbConfig::disk(path);
+        for (key, value) in options {
+            config = config.with_raw_option(key, value);
+        }
+        Self::open(config)
+    }
+
+    /// 灾难恢复：从一份 WAL 文件重放出一个全新的磁盘数据库
+    ///
+    /// # 未实现
+    /// 见 [`WriteAheadLog::open`] 的文档：这个引擎没有独立的 WAL 文件格式
+    /// 可以回放，`open` 总是返回 `Error::Unsupported`，所以下面这个方法的
+    /// 第一步就会用 `?` 把那个错误转发出去，永远不会真的创建数据库或走到
+    /// 循环里。循环本身按请求里描述的样子写完整：跳过 `start_version` 之前
+    /// 的条目、每 100 个版本打一条 `tracing::info!` 进度日志、遇到
+    /// `WalEntry` 解析失败（截断的 WAL）就提前停止——这样等引擎真的提供
+    /// WAL 之后，把 `WriteAheadLog::open` 实现了就能直接用，不需要改这里的
+    /// 调用方代码。
+    pub fn restore_from_wal(
+        wal_path: impl AsRef<std::path::Path>,
+        output_db_path: &str,
+        start_version: Option<u64>,
+    ) -> Result<Db, Error> {
+        let mut reader = WriteAheadLog::open(wal_path)?;
+
+        let mut db = Db::open(DbConfig::disk(output_db_path).with_create(true))?;
+        for entry in &mut reader {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => break, // 截断/损坏的 WAL：停在第一条坏条目之前
+            };
+
+            if start_version.is_some_and(|start| entry.version() < start) {
+                continue;
+            }
+
+            if entry.version() % 100 == 0 {
+                tracing::info!(version = entry.version(), "restore_from_wal progress");
+            }
+
+            let updates: Vec<Update> = (0..entry.update_count())
+                .map(|i| {
+                    let (key, value) = entry.update_at(i);
+                    match value {
+                        Some(v) => Update::put(key, v),
+                        None => Update::delete(key),
+                    }
+                })
+                .collect();
+            db.upsert(&updates, entry.version())?;
+        }
+
+        Ok(db)
+    }
+
     /// 检查是否是磁盘模式
     pub fn is_on_disk(&self) -> bool {
         ffi::db_is_on_disk(&self.inner)
     }
+
+    /// 打开时记录下来的 schema 版本号（见 [`DbConfig::with_schema_version`]），
+    /// 未指定时为 0
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
     
     /// 查找 key 对应的值
-    pub fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, cxx::Exception> {
+    ///
+    /// 如果 `DbConfig::with_max_concurrent_readers` 设置了上限且当前已经有
+    /// 那么多个 `find` 在进行中，立即返回 `Err(Error::ConcurrencyLimitExceeded)`
+    /// 而不是阻塞等待名额。
+    pub fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        if !ffi::db_try_acquire_reader(&self.inner) {
+            return Err(Error::ConcurrencyLimitExceeded);
+        }
+        let _guard = ReaderGuard { inner: &self.inner };
+
         let node = ffi::db_find(&self.inner, key, version)?;
-        
+
         if !ffi::node_has_value(&node) {
             return Ok(None);
         }
-        
+
         let len = ffi::node_value_len(&node);
         if len == 0 {
             return Ok(Some(Vec::new()));
         }
-        
+
         let mut buf = vec![0u8; len];
         let copied = ffi::node_copy_value(&node, &mut buf);
         buf.truncate(copied);
-        
-        Ok(Some(buf))
+
+        Ok(Some(self.decode_stored_value(buf)))
     }
-    
+
+    /// 检查 key 是否存在，不拷贝/分配值本身
+    ///
+    /// 用于只需要访问列表式判断（"这个 key 有没有写过"）而不关心具体值的
+    /// 场景，省掉 [`Db::find`] 里 `node_value_len`/`node_copy_value` 这一段
+    /// 拷贝。和 `find` 一样受 `DbConfig::with_max_concurrent_readers` 限制。
+    pub fn contains_key(&self, key: &[u8], version: u64) -> Result<bool, Error> {
+        if !ffi::db_try_acquire_reader(&self.inner) {
+            return Err(Error::ConcurrencyLimitExceeded);
+        }
+        let _guard = ReaderGuard { inner: &self.inner };
+
+        Ok(ffi::db_contains_key(&self.inner, key, version))
+    }
+
+    /// 对多个 key 分别调用 [`Db::contains_key`]，按 `keys` 的顺序返回
+    pub fn contains_key_batch(&self, keys: &[&[u8]], version: u64) -> Result<Vec<bool>, Error> {
+        keys.iter().map(|key| self.contains_key(key, version)).collect()
+    }
+
+    /// 和 [`Db::contains_key_batch`] 结果完全一样，但只过一次 FFI 边界
+    /// （背后是 [`ffi::db_batch_contains_keys`]），而不是 `keys.len()` 次
+    ///
+    /// 访问列表校验这种一次要查几千个 key 的场景下，省下的是 N 次分别
+    /// 调用 `db_contains_key` 之间的调用开销，不是底层 `find` 本身的 I/O。
+    pub fn contains_keys(&self, keys: &[&[u8]], version: u64) -> Result<Vec<bool>, Error> {
+        if !ffi::db_try_acquire_reader(&self.inner) {
+            return Err(Error::ConcurrencyLimitExceeded);
+        }
+        let _guard = ReaderGuard { inner: &self.inner };
+
+        let raw_keys: Vec<ffi::RawKey> = keys
+            .iter()
+            .map(|key| ffi::RawKey { key_ptr: key.as_ptr(), key_len: key.len() })
+            .collect();
+        let mut results = vec![false; raw_keys.len()];
+        unsafe {
+            ffi::db_batch_contains_keys(
+                &self.inner,
+                raw_keys.as_ptr(),
+                raw_keys.len(),
+                version,
+                results.as_mut_ptr(),
+            );
+        }
+        Ok(results)
+    }
+
+    /// 把 `version` 绑死进一个 [`ReadCursor`]，后续的 `find`/`scan` 都不用
+    /// 再重复传版本号
+    ///
+    /// `&mut self` 是因为 [`ReadCursor::scan`] 内部要
+    /// [`Db::create_async_fifo`]，它本身要求 `&mut self`——cursor 持有一份
+    /// 可变借用才转得过去。
+    pub fn read_at(&mut self, version: u64) -> ReadCursor<'_> {
+        ReadCursor { db: self, version }
+    }
+
+    /// 获取某个账户 key 名下"存储树"的根哈希
+    ///
+    /// # 和真实 Ethereum 存储根的差异
+    /// 真实 Ethereum 里账户树和存储树是两棵独立的 trie：账户叶子的 RLP 值里
+    /// 嵌了一份存储树自己的根哈希。这个引擎不是这样设计的——见
+    /// `depend/monad/category/mpt/update.hpp` 里 `Update` 结构体的注释：一个
+    /// `Update` 的 `value`（账户自己的值）和 `next`（它名下的嵌套存储更新）
+    /// 可以同时设置，共享同一个 key 路径下的同一个节点，被合并进同一棵
+    /// trie。节点的 `root_hash()` 本身就是"账户值 + 嵌套存储子树"两者合并
+    /// 之后的哈希，这个引擎没有任何现成的原语能只取嵌套存储那一部分、排除
+    /// 账户自身的值单独计算一个哈希。
+    ///
+    /// 所以这里返回的是 `account_key` 对应节点的 `root_hash()`——账户存在时
+    /// 能拿到的最接近"存储根"的东西，而不是严格意义上独立于账户自身值的
+    /// 存储树根。如果 `account_key` 在 `version` 下找不到节点或节点没有值，
+    /// 返回 `Ok(None)`。
+    pub fn get_storage_trie_root(
+        &self,
+        account_key: &[u8; 32],
+        version: u64,
+    ) -> Result<Option<[u8; 32]>, Error> {
+        if !ffi::db_try_acquire_reader(&self.inner) {
+            return Err(Error::ConcurrencyLimitExceeded);
+        }
+        let _guard = ReaderGuard { inner: &self.inner };
+
+        let node = ffi::db_find(&self.inner, account_key, version)?;
+        if !ffi::node_has_value(&node) {
+            return Ok(None);
+        }
+
+        let mut hash = [0u8; 32];
+        ffi::node_compute_root_hash(&node, &mut hash);
+        Ok(Some(hash))
+    }
+
+    /// 当前正在进行中的 `find` 调用数
+    pub fn concurrent_reader_count(&self) -> u32 {
+        ffi::db_get_concurrent_readers(&self.inner)
+    }
+
+    /// 动态调整并发 `find` 调用数上限，0 表示不限制
+    pub fn set_max_concurrent_readers(&mut self, max: u32) -> Result<(), Error> {
+        ffi::db_set_max_concurrent_readers(self.inner.pin_mut(), max);
+        Ok(())
+    }
+
+    /// 释放 C++ 侧分配器持有的、当前未使用的内存（malloc_trim 或等价操作）
+    ///
+    /// 返回近似释放的字节数。只影响分配器向 OS 归还的内存，不影响引擎
+    /// 自身缓存的节点数据，也不影响 [`Db::memory_usage`]（那个只统计
+    /// Rust 侧的 value 去重表，和 C++ 分配器完全独立）。
+    ///
+    /// 如果不想手动调用，见 [`DbConfig::with_memory_trim_interval`]。
+    pub fn shrink_memory(&mut self) -> u64 {
+        ffi::db_shrink_memory(&self.inner)
+    }
+
+    /// 用 [`NodeHandlePool`] 里的 NodeHandle 查找 key 对应的值，而不是像
+    /// [`Db::find`] 那样每次都新建一个 NodeHandle
+    ///
+    /// 返回的 [`PooledValue`] 借用 `pool`，drop 时会自动把 NodeHandle 放回
+    /// 池子；key 不存在时直接返回 `Ok(None)`，NodeHandle 立刻归还。
+    pub fn find_with_pool<'a>(
+        &self,
+        pool: &'a mut NodeHandlePool,
+        key: &[u8],
+        version: u64,
+    ) -> Result<Option<PooledValue<'a>>, Error> {
+        if !ffi::db_try_acquire_reader(&self.inner) {
+            return Err(Error::ConcurrencyLimitExceeded);
+        }
+        let _guard = ReaderGuard { inner: &self.inner };
+
+        let mut handle = pool.acquire()?;
+        ffi::db_find_into(&self.inner, key, version, handle.pin_mut())?;
+
+        if !ffi::node_has_value(&handle) {
+            pool.release(handle);
+            return Ok(None);
+        }
+
+        Ok(Some(PooledValue {
+            pool,
+            handle: Some(handle),
+        }))
+    }
+
+    /// 获取通过 [`DbConfig::with_raw_option`] 设置的选项集合
+    ///
+    /// 如实回显调用 [`Db::open`] 时传入的 key/value，不代表引擎真的应用了
+    /// 这些参数——见 [`DbConfig::with_raw_option`] 的文档。
+    pub fn effective_options(&self) -> std::collections::HashMap<String, String> {
+        ffi::db_get_effective_options(&self.inner)
+            .into_iter()
+            .map(|opt| (opt.key, opt.value))
+            .collect()
+    }
+
     /// 获取最新版本号
     pub fn latest_version(&self) -> u64 {
         ffi::db_get_latest_version(&self.inner)
     }
     
     /// 获取最早版本号
+    ///
+    /// 内存模式没有底层的版本历史索引（见 [`Db::load_root`] 的文档），
+    /// 返回的是 [`Db::memory_roots`] 缓存里最小的那个 version。
     pub fn earliest_version(&self) -> u64 {
+        if !self.is_on_disk() {
+            if let Some(min) = self.memory_roots.keys().min() {
+                return *min;
+            }
+        }
         ffi::db_get_earliest_version(&self.inner)
     }
-    
+
     /// 获取历史保留长度
     pub fn history_length(&self) -> u64 {
         ffi::db_get_history_length(&self.inner)
     }
-    
+
+    /// 调整历史保留长度（不重新打开数据库）
+    ///
+    /// # 未实现
+    /// `mpt::Db`（见 `depend/monad/category/mpt/db.hpp`）只有
+    /// `get_history_length()`，没有对应的 setter——`history_length` 是打开
+    /// 数据库时（`OnDiskDbConfig::fixed_history_length`）就定下来的，引擎
+    /// 内部没有"运行时调整保留窗口并立即触发一次性 prune"的接口。所以这
+    /// 里总是返回 [`Error::Unsupported`]；要改变保留长度目前只能重新打开
+    /// 数据库。
+    pub fn set_history_length(&mut self, new_length: u64) -> Result<(), Error> {
+        let _ = new_length;
+        Err(Error::Unsupported(
+            "Db::set_history_length: the engine has no runtime setter for history_length, \
+             only a fixed value chosen at open time",
+        ))
+    }
+
     /// 检查版本是否有效
+    ///
+    /// 内存模式下查的是 [`Db::load_root`] 用到的那个版本缓存，而不是引擎。
     pub fn version_is_valid(&self, version: u64) -> bool {
+        if !self.is_on_disk() {
+            return self.memory_roots.contains_key(&version);
+        }
         ffi::db_version_is_valid(&self.inner, version)
     }
-    
-    /// 更新 finalized 版本（仅磁盘模式）
-    /// 
-    /// finalized 版本表示已被共识确认的版本。
-    /// 
-    /// # 用途
-    /// 1. 配合 `rewind_to_latest_finalized` 选项恢复到一致状态
-    /// 2. 当版本数超过 `history_length` 时触发自动 prune
-    /// 
-    /// # 注意
-    /// - 仅磁盘模式支持
-    /// - 这不是 rollback，不会丢弃指定版本之后的数据
-    pub fn update_finalized_version(&mut self, version: u64) -> Result<(), cxx::Exception> {
-        ffi::db_update_finalized_version(self.inner.pin_mut(), version)
+
+    /// 比 [`Db::version_is_valid`] 语义更清楚的版本分类：不光说"有效还是
+    /// 无效"，还说明无效的原因是被 prune 掉了还是从来没写过
+    ///
+    /// 分类规则：
+    /// - [`VersionStatus::Pruned`]：`version < earliest_version()`——曾经
+    ///   有效，但已经被 [`Db::prune_before_version`]/`update_finalized_version`
+    ///   触发的 prune 丢弃
+    /// - [`VersionStatus::NeverWritten`]：`!version_is_valid(version)` 且不
+    ///   满足上一条——版本号超过 `latest_version()`，或者落在
+    ///   `[earliest_version(), latest_version()]` 区间内但引擎说它无效（比如
+    ///   历史里有洞）
+    /// - [`VersionStatus::Finalized`]：有效，且 `version <= finalized_version()`
+    /// - [`VersionStatus::Accessible`]：有效，但还没被
+    ///   [`Db::update_finalized_version`] 标记为 finalized
+    pub fn version_exists(&self, version: u64) -> Result<VersionStatus, Error> {
+        if !self.version_is_valid(version) {
+            return Ok(if version < self.earliest_version() {
+                VersionStatus::Pruned
+            } else {
+                VersionStatus::NeverWritten
+            });
+        }
+
+        if version <= self.finalized_version() {
+            Ok(VersionStatus::Finalized)
+        } else {
+            Ok(VersionStatus::Accessible)
+        }
     }
-    
-    /// 获取 finalized 版本（仅磁盘模式）
-    /// 
-    /// # 返回
-    /// - 磁盘模式：返回最后设置的 finalized 版本，如果从未设置则返回 `u64::MAX`
-    /// - 内存模式：返回 `u64::MAX`
-    pub fn finalized_version(&self) -> u64 {
-        ffi::db_get_finalized_version(&self.inner)
+
+    /// 丢弃内存模式版本缓存里 `version` 之前的根节点（仅内存模式；磁盘
+    /// 模式的版本历史由引擎按 `history_length` 自己管理，见
+    /// [`Db::update_finalized_version`]）
+    ///
+    /// [`Db::pin_version`] 标记过的版本不会被丢弃，即使它比 `version` 还
+    /// 早。触发的 prune 同样会广播给 [`Db::subscribe_pruning`] 的订阅者
+    /// （被 pin 保留下来的版本不算在 pruned 范围内）。
+    pub fn prune_before_version(&mut self, version: u64) {
+        if self.is_on_disk() {
+            return;
+        }
+        let old_earliest = self.earliest_version();
+        let pinned = &self.pinned_versions;
+        self.memory_roots.retain(|v, _| *v >= version || pinned.contains(v));
+        self.notify_pruned(old_earliest, self.earliest_version());
     }
-    
-    /// 回滚/更新 finalized 版本并触发 prune
-    /// 
-    /// # 参数
-    /// - `version`: 目标版本，必须在 `[earliest_version, latest_version]` 范围内
-    /// 
+
+    /// [`Db::upsert_with_root`] 每次成功写入之后调用，两个限制都复用
+    /// [`Db::prune_before_version`]：
+    /// - [`DbConfig::with_max_memory_versions`] 超了：只保留最新的 n 个内存版本
+    /// - [`DbConfig::with_memory_compaction_threshold`] 超了：丢掉最旧的一半内存版本缓存
+    fn enforce_memory_limits(&mut self, just_written_version: u64) {
+        let _ = just_written_version;
+
+        if let Some(max_versions) = self.max_memory_versions {
+            if self.memory_roots.len() > max_versions {
+                let mut versions: Vec<u64> = self.memory_roots.keys().copied().collect();
+                versions.sort_unstable();
+                let cutoff = versions[versions.len() - max_versions];
+                self.prune_before_version(cutoff);
+            }
+        }
+
+        if let Some(threshold) = self.memory_compaction_threshold {
+            if self.approx_memory_bytes > threshold && self.memory_roots.len() > 1 {
+                let mut versions: Vec<u64> = self.memory_roots.keys().copied().collect();
+                versions.sort_unstable();
+                let cutoff = versions[versions.len() / 2];
+                self.prune_before_version(cutoff);
+                // 这里只是把缓存里的根丢掉，没有把对应的 value 字节数从计数
+                // 里减掉——`approx_memory_bytes` 是单调递增的写入量累计，不是
+                // "当前缓存占用"的精确镜像，见 [`Db::approximate_memory_bytes`]
+                // 的文档。
+            }
+        }
+    }
+
+    /// 标记一个版本不被 [`Db::prune_before_version`] 自动清理
+    ///
+    /// # 磁盘模式的限制
+    /// 底层 `mpt::Db`（见 `depend/monad/category/mpt/db.hpp`）没有"pin"
+    /// 这个概念——`update_finalized_version`/`rewind_to_version` 触发的
+    /// prune 完全由引擎按 `history_length` 自己决定，不会查阅任何 pin
+    /// 列表。这里的 pin 集合在磁盘模式下仍然会被记录、可以查询
+    /// （[`Db::pinned_versions`]），但没有办法真正阻止引擎物理上清理掉
+    /// 超出 `history_length` 的磁盘数据；只有内存模式的
+    /// [`Db::prune_before_version`] 才会实际生效。
+    pub fn pin_version(&mut self, version: u64) -> Result<(), Error> {
+        if !self.version_is_valid(version) {
+            return Err(Error::InvalidVersion(version));
+        }
+        self.pinned_versions.insert(version);
+        Ok(())
+    }
+
+    /// 取消 [`Db::pin_version`] 标记
+    pub fn unpin_version(&mut self, version: u64) -> Result<(), Error> {
+        self.pinned_versions.remove(&version);
+        Ok(())
+    }
+
+    /// 当前被 [`Db::pin_version`] 标记的版本列表，按版本号升序
+    pub fn pinned_versions(&self) -> Vec<u64> {
+        self.pinned_versions.iter().copied().collect()
+    }
+
+    /// 尝试回收不再被 `reachable_from_version` 及更新版本引用的节点，返回
+    /// 估算释放的字节数
+    ///
+    /// # 当前限制
+    /// 这个裁剪后的代码树里，`mpt::Db`（见 `depend/monad/category/mpt/db.hpp`）
+    /// 没有暴露任何"从某个版本出发做 DFS、标记并删除不可达节点"的接口——
+    /// 磁盘模式下的节点回收完全是引擎内部行为，随
+    /// [`Db::update_finalized_version`] 触发的 prune 自动发生，没有一个
+    /// 独立的、可以手动调用的 compaction/GC 步骤，所以磁盘模式下这里直接
+    /// 返回 `Err(Error::Unsupported(..))`，不伪造一个什么都不做却返回 0
+    /// 的假成功。
+    ///
+    /// 内存模式下"GC"本来就是自动的：[`Db::prune_before_version`] 从
+    /// `memory_roots` 里 `retain` 掉的条目会在这次调用结束时被 Rust 的内存
+    /// 分配器立刻释放，不需要额外一步——这里直接转发给它，返回值是调用前后
+    /// [`Db::memory_usage`] 的差值。因为内存模式已经自动做到了
+    /// `DbConfig::with_gc_on_prune` 想要的效果，磁盘模式又没有对应的钩子可
+    /// 挂，这里没有加这样一个两种模式下都没有实际意义的配置项。
+    pub fn gc(&mut self, reachable_from_version: u64) -> Result<u64, Error> {
+        if self.is_on_disk() {
+            return Err(Error::Unsupported(
+                "Db::gc: the underlying mpt::Db has no exposed node-store compaction/GC API; disk-mode pruning already happens automatically via update_finalized_version",
+            ));
+        }
+        let before = self.memory_usage().heap_bytes;
+        self.prune_before_version(reachable_from_version);
+        let after = self.memory_usage().heap_bytes;
+        Ok(before.saturating_sub(after) as u64)
+    }
+
+    /// 带超时的 [`Db::find`]，超过 `timeout` 还没拿到锁就返回
+    /// `Err(Error::Unsupported(..))`
+    ///
+    /// # 当前限制
+    /// 这里没有加一个新的 `Error::Timeout` 变体，因为根本没有东西能触发
+    /// 它：`depend/monad/category/mpt/db.hpp` 里的 `mpt::Db` 没有在 FFI
+    /// 边界上暴露任何 `try_lock_for`/带超时的锁原语（`find` 阻塞的是内部的
+    /// fiber 调度，不是一把可以从 Rust 这边设置超时的 `std::mutex`）。另外
+    /// 这个方法想要覆盖的场景（另一个线程正在做一个长时间的 `upsert`）在
+    /// 安全 Rust 里也走不通——[`Db::find`] 借用 `&self`、[`Db::upsert`] 借
+    /// 用 `&mut self`，同一个 `Db` 不可能让两者真正并发执行，除非调用方自
+    /// 己套一层 `Arc<Mutex<Db>>`，而那样锁永远会在 `upsert` 结束后才释放，
+    /// 不会有机会体现超时语义。与其伪造一个永远不会真正超时的
+    /// `Error::Timeout`，这里直接诚实地返回 `Unsupported`。
+    pub fn try_find(&self, _key: &[u8], _version: u64, _timeout: std::time::Duration) -> Result<Option<Vec<u8>>, Error> {
+        Err(Error::Unsupported(
+            "Db::try_find: the underlying mpt::Db exposes no try_lock_for-style timed lock at the FFI boundary",
+        ))
+    }
+
+    /// 带超时的 [`Db::upsert`]；限制和 [`Db::try_find`] 完全一样
+    pub fn try_upsert(
+        &mut self,
+        _updates: &[Update],
+        _version: u64,
+        _timeout: std::time::Duration,
+    ) -> Result<Node, Error> {
+        Err(Error::Unsupported(
+            "Db::try_upsert: the underlying mpt::Db exposes no try_lock_for-style timed lock at the FFI boundary",
+        ))
+    }
+
+    /// 更新 finalized 版本（仅磁盘模式）
+    /// 
+    /// finalized 版本表示已被共识确认的版本。
+    /// 
+    /// # 用途
+    /// 1. 配合 `rewind_to_latest_finalized` 选项恢复到一致状态
+    /// 2. 当版本数超过 `history_length` 时触发自动 prune——触发时会给
+    ///    [`Db::subscribe_pruning`] 的订阅者广播一条 [`PruningEvent`]
+    ///
+    /// # 注意
+    /// - 仅磁盘模式支持
+    /// - 这不是 rollback，不会丢弃指定版本之后的数据
+    pub fn update_finalized_version(&mut self, version: u64) -> Result<(), cxx::Exception> {
+        let old_earliest = self.earliest_version();
+        ffi::db_update_finalized_version(self.inner.pin_mut(), version)?;
+        self.notify_pruned(old_earliest, self.earliest_version());
+        Ok(())
+    }
+
+    /// 原子地把一批更新写入 `version`，并立即把 `finalized_version` 设置成
+    /// 同一个 `version`
+    ///
+    /// 避免调用方自己先 `upsert` 再单独 `update_finalized_version`——如果进程
+    /// 在两次调用之间崩溃，会留下"已写入但未 finalize"的版本。如果
+    /// `update_finalized_version` 失败，这里会尝试 [`Db::rewind_to_version`]
+    /// 把刚写的 `version` 撤销掉，再把原始错误包进
+    /// `Error::PartialWrite` 返回。
+    ///
+    /// # 当前限制
+    /// "撤销"依赖 [`Db::rewind_to_version`]，而它自己的文档已经说明：目前
+    /// 调用的也是 `update_finalized_version`，只会触发 prune、不会真的丢弃
+    /// 刚写入的 `version`——所以 `Error::PartialWrite` 更多是"如实报告刚才
+    /// 发生了什么"，不能保证调用之后状态真的回到调用前。
+    pub fn upsert_and_finalize(
+        &mut self,
+        root: Option<&Node>,
+        updates: &[Update],
+        version: u64,
+    ) -> Result<Node, Error> {
+        let old_finalized = self.finalized_version();
+        let new_root = self.upsert_with_root(root, updates, version)?;
+        if let Err(e) = self.update_finalized_version(version) {
+            let cause = Error::from(e);
+            let rewind_target = if old_finalized == u64::MAX { 0 } else { old_finalized };
+            let _ = self.rewind_to_version(rewind_target);
+            return Err(Error::PartialWrite { cause: Box::new(cause) });
+        }
+        Ok(new_root)
+    }
+
+    /// 获取 finalized 版本（仅磁盘模式）
+    /// 
+    /// # 返回
+    /// - 磁盘模式：返回最后设置的 finalized 版本，如果从未设置则返回 `u64::MAX`
+    /// - 内存模式：返回 `u64::MAX`
+    pub fn finalized_version(&self) -> u64 {
+        ffi::db_get_finalized_version(&self.inner)
+    }
+    
+    /// 回滚/更新 finalized 版本并触发 prune
+    /// 
+    /// # 参数
+    /// - `version`: 目标版本，必须在 `[earliest_version, latest_version]` 范围内
+    /// 
     /// # 注意
     /// 当前实现调用 `update_finalized_version`，会触发 prune 但不会丢弃后续版本。
     /// 完整 rollback（丢弃后续版本）需要使用 CLI 工具: `monad_mpt --rewind-to <version>`
+    ///
+    /// 同 `update_finalized_version`，触发的 prune 会广播给
+    /// [`Db::subscribe_pruning`] 的订阅者。
     pub fn rewind_to_version(&mut self, version: u64) -> Result<(), cxx::Exception> {
-        ffi::db_rewind_to_version(self.inner.pin_mut(), version)
+        let old_earliest = self.earliest_version();
+        ffi::db_rewind_to_version(self.inner.pin_mut(), version)?;
+        self.notify_pruned(old_earliest, self.earliest_version());
+        Ok(())
     }
-    
+
+    /// 崩溃恢复：把 `finalized_version` 之后可能存在部分写入的版本丢掉
+    ///
+    /// # 当前实现的限制
+    /// 底层只有 [`Db::rewind_to_version`]，而它自己的文档已经说明：目前
+    /// 调用的是 `update_finalized_version`，只会触发 prune、不会真的丢弃
+    /// `finalized_version` 之后的版本——完整 rollback 需要 CLI 工具
+    /// `monad_mpt --rewind-to <version>`。所以调用完这个方法之后
+    /// [`Db::latest_version`] 不一定会变成 `finalized_version`；返回值是
+    /// "`finalized_version` 之后还有多少个版本" 算出来的理论丢弃数，不是
+    /// 已经实测验证过的结果。
+    ///
+    /// `finalized_version() == u64::MAX`（从未设置过）或者已经
+    /// `>= latest_version()` 时什么都不做，返回 0。
+    pub fn point_in_time_restore(&mut self) -> Result<u64, Error> {
+        let finalized = self.finalized_version();
+        if finalized == u64::MAX {
+            return Ok(0);
+        }
+        let latest = self.latest_version();
+        if finalized >= latest {
+            return Ok(0);
+        }
+        self.rewind_to_version(finalized)?;
+        Ok(latest - finalized)
+    }
+
     /// 加载指定版本的根节点
+    ///
+    /// # 缓存优先
+    /// 先查 [`Db::memory_roots`]：内存模式下 [`Db::upsert_with_root`] 每次
+    /// 成功后都会往里缓存一份；磁盘模式下默认不缓存，但
+    /// [`Db::swap_root`] 会把换上去的根缓存进来（因为 `db_swap_root` 和
+    /// 其余 upsert 系列 FFI 调用一样固定传 `write_root=false`，引擎自己
+    /// 的按版本根记录里查不到它）。缓存未命中时落到下面这次 FFI 调用，
+    /// 和之前的行为一致（内存模式下只有版本已被
+    /// [`Db::prune_before_version`] 丢弃才会走到这里，得到原来的错误）。
     pub fn load_root(&self, version: u64) -> Result<Node, cxx::Exception> {
+        if let Some(node) = self.memory_roots.get(&version) {
+            return Ok(node.clone());
+        }
         let inner = ffi::db_load_root_for_version(&self.inner, version)?;
         Ok(Node { inner })
     }
-    
+
+    /// `load_root(latest_version())` 的简写
+    pub fn latest_root(&self) -> Result<Node, Error> {
+        Ok(self.load_root(self.latest_version())?)
+    }
+
+    /// `load_root(earliest_version())` 的简写
+    pub fn earliest_root(&self) -> Result<Node, Error> {
+        Ok(self.load_root(self.earliest_version())?)
+    }
+
+    /// `load_root(finalized_version())` 的简写，`finalized_version()` 还未
+    /// 设置过（`u64::MAX`）时返回 `None`
+    pub fn finalized_root(&self) -> Result<Option<Node>, Error> {
+        let finalized = self.finalized_version();
+        if finalized == u64::MAX {
+            return Ok(None);
+        }
+        Ok(Some(self.load_root(finalized)?))
+    }
+
+    /// 检查最新版本的 trie 是不是空的
+    ///
+    /// `latest_version() == 0`（从未写过）或者
+    /// `load_root(latest_version())` 的根哈希是全零（最后一次写入把所有
+    /// key 都删空了）都算空
+    pub fn is_empty(&self) -> bool {
+        let latest = self.latest_version();
+        if latest == 0 {
+            return true;
+        }
+        match self.load_root(latest) {
+            Ok(root) => root.root_hash() == [0u8; 32],
+            Err(_) => true,
+        }
+    }
+
+    /// [`Db::is_empty`] 的历史版本版：检查指定版本的 trie 是不是空的
+    pub fn is_empty_at_version(&self, version: u64) -> Result<bool, Error> {
+        if version == 0 {
+            return Ok(true);
+        }
+        let root = self.load_root(version)?;
+        Ok(root.root_hash() == [0u8; 32])
+    }
+
+    /// 返回所有版本和对应根哈希的完整列表，按版本号升序排列
+    ///
+    /// # 当前限制
+    /// 底层 `mpt::Db` 没有"一次 C++ 调用批量返回多个版本的根哈希"的接口，
+    /// 这里没有按字面意思新增一个虚构的 `ffi::db_get_timeline_batch`/
+    /// `TimelineHandle`——每个版本的根仍然是各自一次
+    /// [`Db::load_root`]（`db_load_root_for_version`）。内存模式下直接用
+    /// [`Db::memory_roots`] 缓存里存在过的 key 集合，不会假设版本号连续；
+    /// 磁盘模式下假设 `[earliest_version(), latest_version()]` 之间的版本
+    /// 号是连续的（和 [`Db::earliest_version`]/[`Db::latest_version`] 文档
+    /// 里的语义一致），没有专门验证过存在版本号间隙（比如部分历史被
+    /// prune 之后留下洞）的场景。
+    pub fn timeline(&self) -> Result<Vec<(u64, [u8; 32])>, Error> {
+        let versions: Vec<u64> = if !self.is_on_disk() {
+            let mut versions: Vec<u64> = self.memory_roots.keys().copied().collect();
+            versions.sort_unstable();
+            versions
+        } else {
+            let earliest = self.earliest_version();
+            let latest = self.latest_version();
+            if latest == u64::MAX || earliest > latest {
+                Vec::new()
+            } else {
+                (earliest..=latest).collect()
+            }
+        };
+
+        versions
+            .into_iter()
+            .map(|version| Ok((version, self.load_root(version)?.root_hash())))
+            .collect()
+    }
+
+    /// 对 [`Db::timeline`] 列出的每一个版本都加载一次根节点并调用
+    /// [`Node::verify_consistency`]，返回验证过的根节点数量
+    ///
+    /// 见 [`Node::verify_consistency`] 的限制：正常通过 [`Db::load_root`]
+    /// 拿到的树节点总是会通过验证（没有另外一份独立存储的"缓存哈希"可以
+    /// 交叉校验，只要重新计算哈希没有失败就算通过），所以只要版本都能被
+    /// [`Db::load_root`] 成功加载，这个方法总会返回 `Ok(timeline().len())`。
+    pub fn verify_all_roots(&self) -> Result<usize, Error> {
+        let timeline = self.timeline()?;
+        let mut verified = 0usize;
+        for (version, _root_hash) in timeline {
+            let root = self.load_root(version)?;
+            if root.verify_consistency() {
+                verified += 1;
+            }
+        }
+        Ok(verified)
+    }
+
+    /// 惰性地遍历 `[from, to]` 区间的版本，产出每个版本的根哈希以及它和
+    /// 前一个版本之间的衔接关系，见 [`VersionProof`]/[`VersionProofIter`]
+    ///
+    /// `from > 0` 时会立即（非惰性）加载一次 `from - 1` 的根，用来算出
+    /// 区间第一个版本真正的 `parent_root_hash`；如果 `from - 1` 已经被
+    /// [`Db::prune_before_version`] 丢弃，这一步会失败并把错误直接返回，
+    /// 而不是把第一个版本的 `parent_root_hash` 悄悄填成 `None`（那样会让
+    /// [`VersionProof::verify_chain`] 对一条其实不完整的链误判为"衔接上了"）。
+    pub fn iter_version_proofs(&self, from: u64, to: u64) -> Result<VersionProofIter<'_>, Error> {
+        let prev_root_hash = if from == 0 { None } else { Some(self.load_root(from - 1)?.root_hash()) };
+        Ok(VersionProofIter::new(self, from, to, prev_root_hash))
+    }
+
+    /// 原子地把 `version` 的根整体替换成 `new_root`，不经过逐 key 的
+    /// `UpdateList`
+    ///
+    /// 基于 `db_swap_root`（[`mpt::Db::copy_trie`] 的空前缀整根拷贝），比
+    /// `upsert` 更直接：创世区块导入之类"整棵状态树一次性换掉"的场景不需要
+    /// 把新状态拆成一条条 key 的更新再重放一遍。换下来的旧根仍然留在
+    /// `version - 1`（`swap_root` 只改 `version` 这一个版本的根，不触碰
+    /// 别的版本），调用方可以照旧用 [`Db::load_root`] 查到。
+    ///
+    /// 返回替换前 `version` 上缓存的根；如果 `version` 之前没有被
+    /// [`Db::swap_root`]、[`Db::upsert_with_root`]（内存模式）缓存过根，
+    /// 就没有真正意义上的"旧根"可以返回，这时退化为返回换上去的新根本身。
+    pub fn swap_root(&mut self, new_root: Node, version: u64) -> Result<Node, Error> {
+        let previous = self.memory_roots.get(&version).cloned();
+
+        let inner = ffi::db_swap_root(self.inner.pin_mut(), &new_root.inner, version)?;
+        let node = Node { inner };
+        self.memory_roots.insert(version, node.clone());
+
+        Ok(previous.unwrap_or(node))
+    }
+
+    /// 按 32 字节 Keccak256 哈希查找任意版本的节点（不依赖 key/version）
+    ///
+    /// # 未实现
+    /// 底层 `mpt::Db`（见 `depend/monad/category/mpt/db.hpp`）只支持按
+    /// `(NibblesView 路径, version)` 查找（`find`/`load_root_for_version`），
+    /// 没有维护一个哈希到节点的全局索引——Merkle 根哈希只用来做完整性
+    /// 校验，不是一个可以反查的存储键，所以这里没有字节可以查，总是返回
+    /// [`Error::Unsupported`]。无状态同步协议（snap sync 等）需要的
+    /// hash-addressed 查找要等引擎侧加上这样的索引才能实现。
+    pub fn get_node_by_hash(&self, hash: &[u8; 32]) -> Result<Option<Node>, Error> {
+        let _ = hash;
+        Err(Error::Unsupported(
+            "Db::get_node_by_hash: this engine has no hash-to-node index; nodes are only \
+             addressable by (key path, version)",
+        ))
+    }
+
+    /// 从任意历史版本分叉出一个独立的、可写的内存数据库
+    ///
+    /// EVM 模拟等场景需要在任意历史区块上 fork 出一个沙盒环境，而不仅仅是最新版本。
+    /// 分叉出的 DB 与原 DB 完全独立，对分叉副本的写入不会影响原 DB。
+    ///
+    /// # 参数
+    /// - `version`: 要分叉的历史版本号，必须是当前 DB 上的有效版本
+    ///
+    /// # 返回
+    /// 一个新的内存数据库，其 `earliest_version` 等于 `version`
+    pub fn fork_at_version(&self, version: u64) -> Result<Db, Error> {
+        if !self.version_is_valid(version) {
+            return Err(Error::InvalidVersion(version));
+        }
+
+        let root = self.load_root(version)?;
+        let mut forked = Db::open_memory()?;
+        forked.upsert_with_root(Some(&root), &[], version)?;
+        Ok(forked)
+    }
+
+    /// 把 `self` 在 `src_version` 上的全部 key/value 复制到另一个 DB
+    ///
+    /// 内部靠 [`ScanIter`]（基于 `AsyncFifo` 的遍历分页机制，和
+    /// [`AsyncFifo::traverse_stream`] 同一套底层原语）按 1000 条一批遍历
+    /// `self`，再批量 `upsert` 进 `dst`。这个复制不是原子的：如果 `self`
+    /// 在扫描过程中有并发写入，复制结果可能是一个介于扫描开始和结束之间
+    /// 的不一致快照，调用方需要自己保证没有并发写（或者能接受这一点）。
+    ///
+    /// 创建扫描用的 `AsyncFifo` 需要 `&mut self`（见
+    /// [`Db::create_async_fifo`]），所以这里没法按字面意思用 `&self`。
+    pub fn copy_to(&mut self, dst: &mut Db, src_version: u64, dst_version: u64) -> Result<Node, Error> {
+        let fifo = self.create_async_fifo()
+            .map_err(|_| Error::Unsupported("copy_to: failed to create AsyncFifo for source scan"))?;
+        let mut scan = ScanIter::new(fifo, src_version);
+
+        let mut root: Option<Node> = None;
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(1000);
+
+        for pair in &mut scan {
+            batch.push(pair);
+            if batch.len() >= 1000 {
+                root = Some(copy_batch_into(dst, root.as_ref(), &batch, dst_version)?);
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            root = Some(copy_batch_into(dst, root.as_ref(), &batch, dst_version)?);
+        }
+
+        match root {
+            Some(root) => Ok(root),
+            None => Ok(dst.upsert_with_root(None, &[], dst_version)?),
+        }
+    }
+
+    /// [`Db::copy_to`]，但目标是一个新建的磁盘 DB（路径 `dst_path`），而不是
+    /// 一个已经打开的 [`Db`]
+    pub fn copy_to_path(&mut self, src_version: u64, dst_path: &str) -> Result<(), Error> {
+        let mut dst = Db::open(DbConfig::disk(dst_path).with_create(true))?;
+        self.copy_to(&mut dst, src_version, src_version)?;
+        Ok(())
+    }
+
+    /// 在 `path` 创建一份 [`Db::latest_version`] 的持久化快照，习惯上 `path`
+    /// 以 `.checkpoint` 结尾，这样 [`Db::list_checkpoints`] 才能找到它
+    ///
+    /// # 当前限制
+    /// `depend/monad/category/mpt/` 底下的存储引擎不是 RocksDB，没有
+    /// "硬链接整个 LSM 目录" 那种 O(1) checkpoint 原语——这里是
+    /// [`Db::copy_to_path`] 的一个薄包装，本质是把 `latest_version()` 整棵
+    /// 树逐条 key/value 拷过去，耗时和数据量成正比，不是 RocksDB checkpoint
+    /// 那种近乎瞬间完成的操作。
+    pub fn checkpoint(&mut self, path: impl Into<String>) -> Result<(), Error> {
+        let latest = self.latest_version();
+        self.copy_to_path(latest, &path.into())
+    }
+
+    /// 从 [`Db::checkpoint`] 创建的快照目录打开一个新的磁盘 [`Db`]
+    ///
+    /// 打开后只能看到做 checkpoint 那一刻的版本（见 [`Db::checkpoint`]）；
+    /// 之后对原 DB 的写入不会出现在这里，因为两者是完全独立的两份数据。
+    pub fn open_from_checkpoint(checkpoint_path: &str) -> Result<Db, Error> {
+        Db::open(DbConfig::disk(checkpoint_path))
+    }
+
+    /// 扫描 `db_path` 目录下名字以 `.checkpoint` 结尾的子目录，返回它们的
+    /// 完整路径（不保证顺序）
+    ///
+    /// 只是按 [`Db::checkpoint`] 的命名约定做文件系统遍历，不依赖任何
+    /// 存储引擎的 manifest，所以手动改过名字的目录会被漏掉或者误报。
+    pub fn list_checkpoints(db_path: &str) -> Result<Vec<String>, Error> {
+        let mut checkpoints = Vec::new();
+        for entry in std::fs::read_dir(db_path).map_err(Error::Io)? {
+            let entry = entry.map_err(Error::Io)?;
+            if entry.path().is_dir() && entry.file_name().to_string_lossy().ends_with(".checkpoint") {
+                checkpoints.push(entry.path().to_string_lossy().into_owned());
+            }
+        }
+        Ok(checkpoints)
+    }
+
+    /// 删掉 `self` 在 `version` 上所有以 `prefix` 开头的 key，`prefix` 为空
+    /// 时删掉整棵树
+    ///
+    /// 内部靠 [`ScanIter`]（和 [`Db::copy_to`] 同一套基于 `AsyncFifo` 的遍历
+    /// 分页机制，只是带上了 `prefix`）先扫出所有匹配的 key，再把它们转成
+    /// 一批 `Update::delete` 调用 [`Db::upsert_with_root`]。和 `copy_to` 一样，
+    /// 扫描和随后的删除之间不是原子的：调用方需要自己保证扫描期间没有并发
+    /// 写以 `prefix` 开头的 key。
+    ///
+    /// 返回新的根节点和实际删掉的 key 数量。创建扫描用的 `AsyncFifo` 需要
+    /// `&mut self`（见 [`Db::create_async_fifo`]），所以这里没法按字面意思
+    /// 用 `&self`。
+    pub fn delete_prefix(
+        &mut self,
+        root: Option<&Node>,
+        prefix: &[u8],
+        version: u64,
+    ) -> Result<(Node, u64), Error> {
+        let fifo = self.create_async_fifo()
+            .map_err(|_| Error::Unsupported("delete_prefix: failed to create AsyncFifo for prefix scan"))?;
+        let scan = ScanIter::with_prefix(fifo, version, prefix.to_vec());
+        let keys: Vec<Vec<u8>> = scan.map(|(key, _value)| key).collect();
+
+        if keys.is_empty() {
+            let unchanged = match root {
+                Some(root) => root.clone(),
+                None => self.upsert_with_root(None, &[], version)?,
+            };
+            return Ok((unchanged, 0));
+        }
+
+        let deletes: Vec<Update> = keys.iter().map(|k| Update::delete(k)).collect();
+        let new_root = self.upsert_with_root(root, &deletes, version)?;
+        Ok((new_root, keys.len() as u64))
+    }
+
+    /// [`Db::delete_prefix`] 会删掉多少个 key，但不真的删
+    ///
+    /// 扫描范围和 `delete_prefix` 完全一样，只是扫完直接数数、不构造
+    /// `Update::delete`、不调用 `upsert`。`root` 目前没有用到：扫描靠的是
+    /// `self` 在 `version` 上已经落盘/提交的状态（见 [`AsyncFifo::
+    /// submit_traverse`]），不是某个游离的 [`Node`]；这里保留这个参数只是
+    /// 为了跟 `delete_prefix` 的签名对齐，方便调用方在真正删除前先用同一套
+    /// 参数做一次预演。
+    pub fn delete_prefix_dry_run(
+        &mut self,
+        root: &Node,
+        prefix: &[u8],
+        version: u64,
+    ) -> Result<u64, Error> {
+        let _ = root;
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("delete_prefix_dry_run: failed to create AsyncFifo for prefix scan")
+        })?;
+        let scan = ScanIter::with_prefix(fifo, version, prefix.to_vec());
+        Ok(scan.count() as u64)
+    }
+
+    /// 按字典序返回 `version` 下所有的 key
+    ///
+    /// 复用 [`Db::scan`]/[`Db::delete_prefix`] 同一套 [`ScanIter`] 分页遍历
+    /// （空 `prefix` 等价于扫全表）。`ScanIter` 内部是 `bridge_fifo.cpp` 里
+    /// `FifoTraverseMachine` 的 `down`/`up` 回调实现的 DFS：按 nibble 值从
+    /// 小到大依次下探分支，和 MPT 本身"同一层的子节点按 nibble 排序"的结构
+    /// 保持一致——这就是为什么扫出来的 key 天然按字典序排列，不需要额外
+    /// 排序。和仓库里其它基于 `ScanIter` 的方法一样，条目本身不包 `Result`：
+    /// 唯一会失败的地方是建立扫描用的 `AsyncFifo`，已经体现在外层的
+    /// `Result` 里了。
+    pub fn iter_keys_sorted(
+        &mut self,
+        version: u64,
+    ) -> Result<impl Iterator<Item = Vec<u8>>, Error> {
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("iter_keys_sorted: failed to create AsyncFifo for scan")
+        })?;
+        Ok(ScanIter::new(fifo, version).map(|(key, _value)| key))
+    }
+
+    /// 和 [`Db::iter_keys_sorted`] 一样按字典序遍历，但同时带出 value
+    pub fn iter_key_values_sorted(
+        &mut self,
+        version: u64,
+    ) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>, Error> {
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("iter_key_values_sorted: failed to create AsyncFifo for scan")
+        })?;
+        Ok(ScanIter::new(fifo, version))
+    }
+
+    /// 和 [`Db::iter_key_values_sorted`] 一样按字典序遍历，但只扫
+    /// `prefix` 开头的那部分 key，并只要 value——复用
+    /// [`Db::delete_prefix_dry_run`] 同一套基于 `ScanIter::with_prefix`
+    /// 的分页遍历，`map` 掉 key 那一半，不分配多余的 key `Vec`。和
+    /// [`Db::iter_keys_sorted`]/[`Db::iter_key_values_sorted`] 一样，条目
+    /// 本身不包 `Result`——唯一会失败的地方是建立扫描用的 `AsyncFifo`，
+    /// 已经体现在外层的 `Result` 里了。
+    pub fn iter_values_for_prefix(
+        &mut self,
+        prefix: &[u8],
+        version: u64,
+    ) -> Result<impl Iterator<Item = Vec<u8>>, Error> {
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("iter_values_for_prefix: failed to create AsyncFifo for scan")
+        })?;
+        let scan = ScanIter::with_prefix(fifo, version, prefix.to_vec());
+        Ok(scan.map(|(_key, value)| value))
+    }
+
+    /// [`Db::iter_values_for_prefix`]，但只要 key
+    pub fn iter_keys_for_prefix(
+        &mut self,
+        prefix: &[u8],
+        version: u64,
+    ) -> Result<impl Iterator<Item = Vec<u8>>, Error> {
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("iter_keys_for_prefix: failed to create AsyncFifo for scan")
+        })?;
+        let scan = ScanIter::with_prefix(fifo, version, prefix.to_vec());
+        Ok(scan.map(|(key, _value)| key))
+    }
+
+    /// 校验 `a` 在 `a_version` 下和 `b` 在 `b_version` 下状态完全一致，不用
+    /// 逐 key 比较就能快速确认"相等"这个常见情形
+    ///
+    /// 先确认两边的 `a_version`/`b_version` 都能 [`Db::load_root`] 出来——
+    /// 任何一边失败（比如传了个从没写过的版本号）都直接返回
+    /// [`Error::Ffi`]，不会把"两边都加载失败"误判成"两边相等"。都加载
+    /// 成功后比较 `root_hash()`：一致就直接 `Ok(())`，不用扫描任何数据。
+    /// 只有 hash 不一致时才分别走一遍 [`Db::iter_key_values_sorted`]，收集
+    /// 成有序 map 后做一次归并比较，得到具体差在哪些 key 上。
+    ///
+    /// # 当前限制
+    /// 请求里设想的"并行扫描两棵树"在这个引擎里没有对应的原语（见
+    /// [`Db::count_reachable_nodes_parallel`] 的 `# 当前限制`：这里的
+    /// `TraverseMachine` 遍历本身就是单线程的），所以这里对 `a`、`b` 依次
+    /// 扫描，不是真的两边并发扫描。
+    ///
+    /// `a`、`b` 需要 `&mut`，原因和 [`Db::iter_key_values_sorted`] 一样：
+    /// 扫描要先 [`Db::create_async_fifo`]，它本身要求 `&mut self`。
+    pub fn assert_equal_at_version(
+        a: &mut Db,
+        a_version: u64,
+        b: &mut Db,
+        b_version: u64,
+    ) -> Result<(), Error> {
+        let a_root = a.load_root(a_version)?;
+        let b_root = b.load_root(b_version)?;
+        if a_root.root_hash() == b_root.root_hash() {
+            return Ok(());
+        }
+
+        let a_entries: std::collections::BTreeMap<Vec<u8>, Vec<u8>> = a
+            .iter_key_values_sorted(a_version)
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let b_entries: std::collections::BTreeMap<Vec<u8>, Vec<u8>> = b
+            .iter_key_values_sorted(b_version)
+            .map(Iterator::collect)
+            .unwrap_or_default();
+
+        let mut report = InequalityReport::default();
+        for (key, a_value) in &a_entries {
+            match b_entries.get(key) {
+                Some(b_value) if b_value == a_value => {}
+                Some(b_value) => report.keys_with_different_values.push((
+                    key.clone(),
+                    a_value.clone(),
+                    b_value.clone(),
+                )),
+                None => report.keys_only_in_a.push(key.clone()),
+            }
+        }
+        for key in b_entries.keys() {
+            if !a_entries.contains_key(key) {
+                report.keys_only_in_b.push(key.clone());
+            }
+        }
+
+        if report.keys_only_in_a.is_empty()
+            && report.keys_only_in_b.is_empty()
+            && report.keys_with_different_values.is_empty()
+        {
+            return Ok(());
+        }
+        Err(Error::Unequal(report))
+    }
+
+    /// 按 [`Migration`] 把 `from_db_version` 下的全部 key/value 重写一遍，
+    /// 落到 `to_db_version` 上
+    ///
+    /// 基于 [`Db::iter_key_values_sorted`] 扫出 `from_db_version` 下的全部
+    /// 条目，对每一条分别调用 `migrate_key`/`migrate_value`：
+    /// - `migrate_key` 返回 `None`：这条记录整体丢弃（原 key 对应的记录
+    ///   从 `to_db_version` 里删掉）
+    /// - `migrate_key` 返回和原 key 不同的新 key：原 key 在 `to_db_version`
+    ///   里删除，新 key 写入迁移后的值
+    /// - `migrate_value` 返回 `None`：该记录的值被丢弃，等价于删除（用的
+    ///   是 `migrate_key` 算出来的那个 key，不管它是不是和原 key 一样）
+    ///
+    /// 返回迁移后的新根和实际扫到的 key 数量（不是实际写入/删除的 update
+    /// 数量——一条记录改名会产生两个 update）。
+    pub fn migrate_schema(
+        &mut self,
+        migration: &dyn Migration,
+        from_db_version: u64,
+        to_db_version: u64,
+    ) -> Result<(Node, u64), Error> {
+        let entries: Vec<(Vec<u8>, Vec<u8>)> =
+            self.iter_key_values_sorted(from_db_version)?.collect();
+        let migrated_count = entries.len() as u64;
+
+        let mut owned_updates: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::new();
+        for (key, value) in entries {
+            let new_key = migration.migrate_key(&key);
+            let new_value = migration.migrate_value(&key, &value);
+
+            match new_key {
+                None => owned_updates.push((key, None)),
+                Some(new_key) => {
+                    if new_key != key {
+                        owned_updates.push((key, None));
+                    }
+                    owned_updates.push((new_key, new_value));
+                }
+            }
+        }
+
+        let updates: Vec<Update> = owned_updates
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => Update::put(key, value),
+                None => Update::delete(key),
+            })
+            .collect();
+
+        let new_root = self.upsert_with_root(None, &updates, to_db_version)?;
+        Ok((new_root, migrated_count))
+    }
+
+    /// 只遍历 `prefix` 子树里前 `max_depth` 层 trie 节点，用于只需要列出
+    /// 子树"前几层"的场景（比如只列账户前缀、不展开每个账户的存储树）
+    ///
+    /// # 参数
+    /// `root` 和 [`Db::delete_prefix_dry_run`] 一样没有用到：底层扫描靠的
+    /// 是 `self` 在 `version` 上已经落盘/提交的状态（见
+    /// [`AsyncFifo::submit_traverse_subtrie`]），不是某个游离的 [`Node`]；
+    /// 保留这个参数只是为了让签名和调用方已经拿在手里的根对齐。
+    ///
+    /// # 返回
+    /// 和 [`Db::iter_key_values_sorted`] 一样按字典序产出 `(key, value)`，
+    /// 但 `value` 是 `Option`：深度边界处自身没有 value、但子树下面还有
+    /// 数据的节点会被报告为 `(key, None)`，而不是像 `iter_key_values_sorted`
+    /// 那样直接跳过。深度按 trie 节点的下探次数计，不是 nibble 数，见
+    /// [`AsyncFifo::submit_traverse_subtrie`] 的文档。
+    pub fn traverse_subtrie(
+        &mut self,
+        root: &Node,
+        prefix: &[u8],
+        max_depth: u32,
+        version: u64,
+    ) -> Result<SubTrieTraversal, Error> {
+        let _ = root;
+        let fifo = self.create_async_fifo().map_err(|_| {
+            Error::Unsupported("traverse_subtrie: failed to create AsyncFifo for scan")
+        })?;
+        Ok(SubTrieTraversal::new(fifo, prefix.to_vec(), max_depth, version))
+    }
+
+    /// 把 `root` 的结构 dump 成一份 Graphviz DOT 文本
+    ///
+    /// # 未实现的部分
+    /// 调试 trie 结构本来应该是一次 DFS：从 `root` 出发，按分支逐层展开，
+    /// 每个分支节点画出指向各个子节点的边。但 [`Node`]（见其 `nibble_path`
+    /// 方法的文档）只是对 `mpt::Node` 单个节点的包装，没有任何 FFI 原语能
+    /// "取出某个分支下标对应的子节点"——`bridge.hpp`/`bridge.cpp` 里现有的
+    /// 节点相关函数只有 `node_has_value`/`node_value_len`/`node_copy_value`/
+    /// `node_data_len`/`node_copy_data`/`node_nibble_path_len`/
+    /// `node_copy_nibble_path`/`node_compute_root_hash`/`node_to_rlp`，都是
+    /// "读这一个节点自己的信息"，没有一个暴露子节点指针或子节点哈希列表。
+    /// 所以这里没法做真正的 DFS，只能把 `root` 这一个节点本身画成 DOT 里
+    /// 唯一的一个顶点——标签里放的是它自己压缩边的前 4 个 nibble
+    /// （[`Node::nibble_path`]）和 value 长度，没有子节点、也没有连向子节点
+    /// 的边。
+    pub fn dump_trie_to_graphviz(
+        &self,
+        root: &Node,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<(), Error> {
+        let nibbles = root.nibble_path();
+        let nibble_prefix: String = nibbles
+            .iter()
+            .take(2)
+            .map(|b| format!("{:x}{:x}", b >> 4, b & 0xf))
+            .collect::<Vec<_>>()
+            .join("")
+            .chars()
+            .take(4)
+            .collect();
+        let value_len = root.value().map_or(0, |v| v.len());
+
+        writeln!(writer, "digraph trie {{").map_err(Error::Io)?;
+        writeln!(
+            writer,
+            "  n0 [label=\"root\\nnibbles={nibble_prefix}\\nvalue_len={value_len}\"];"
+        )
+        .map_err(Error::Io)?;
+        writeln!(writer, "}}").map_err(Error::Io)?;
+        Ok(())
+    }
+
+    /// [`Db::dump_trie_to_graphviz`]，但直接返回一个 `String`
+    pub fn dump_trie_to_dot_string(&self, root: &Node) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.dump_trie_to_graphviz(root, &mut buf)?;
+        String::from_utf8(buf).map_err(|_| Error::Unsupported("dump_trie_to_dot_string: DOT output was not valid UTF-8"))
+    }
+
+    /// 数 `root` 在 `version` 下的 key 数量
+    ///
+    /// 只有叶子节点才持有值，所以这是一次只数叶子的 DFS（C++ 侧
+    /// `db_count_leaves`，和 [`Db::merge_roots`] 内部的 `CollectLeavesMachine`
+    /// 同构，但不重建 key、不拷贝 value），比 `scan("").count()`（逐条把
+    /// value 拷进 `Completion::value` 再跨 FIFO 传回来）快得多，尤其是在
+    /// value 比较大的时候。
+    pub fn count_keys(&mut self, root: &Node, version: u64) -> Result<u64, Error> {
+        Ok(ffi::db_count_leaves(self.inner.pin_mut(), &*root.inner, version)?)
+    }
+
+    /// 单线程数 `root` 在 `version` 下可达的（有值的）节点数量，直接
+    /// 委托给 [`Db::count_keys`] 背后的同一个 `db_count_leaves`
+    ///
+    /// `version` 必须是 `root` 实际所属的版本——这个参数同时会被 C++
+    /// 侧用来做版本校验（见 `db.hpp`），传错版本不会报错，但可能数出
+    /// 错误或不完整的结果，所以这里要求调用方显式传入，不代为猜测
+    /// [`Db::latest_version`]
+    pub fn count_reachable_nodes(&mut self, root: &Node, version: u64) -> u64 {
+        ffi::db_count_leaves(self.inner.pin_mut(), &*root.inner, version).unwrap_or(0)
+    }
+
+    /// [`Db::count_reachable_nodes`]，但接受一个 `threads` 参数表达"并行
+    /// 计数"的意图
+    ///
+    /// `version` 的要求和 [`Db::count_reachable_nodes`] 一样：必须是
+    /// `root` 实际所属的版本。
+    ///
+    /// # 当前限制
+    /// 这个引擎的 `mpt::TraverseMachine` 遍历是单线程、异步 I/O 并发的
+    /// （同一个线程交错推进多个遍历请求），没有多 CPU 线程work-stealing
+    /// 的并行 DFS 原语可用。`threads` 只会被提升到至少 1 再传给 C++ 侧，
+    /// C++ 侧忽略它，不会真的用多个线程去分摊遍历——结果和
+    /// [`Db::count_reachable_nodes`] 完全一样，也不会更快。
+    pub fn count_reachable_nodes_parallel(&mut self, root: &Node, version: u64, threads: u32) -> u64 {
+        ffi::db_count_nodes_parallel(self.inner.pin_mut(), &*root.inner, version, threads.max(1))
+            .unwrap_or(0)
+    }
+
     /// 批量更新（从空树开始）
     /// 
     /// # 参数
@@ -441,11 +2877,23 @@ impl Db {
         updates: &[Update],
         version: u64,
     ) -> Result<Node, cxx::Exception> {
+        // 启用去重时，先把 updates 中的 value 替换成它们的 keccak256 哈希
+        // （原始 value 保留一份在 self.dedup_store 中），再走下面不变的构建逻辑
+        let dedup_owned = self.value_dedup.then(|| dedup_updates(updates, &mut self.dedup_store));
+        let dedup_borrowed = dedup_owned.as_ref().map(|owned| owned_to_borrowed(owned));
+        let updates: &[Update] = dedup_borrowed.as_deref().unwrap_or(updates);
+
+        // 启用加密时，对（可能已经被去重替换成哈希的）value 做 AES-256-GCM
+        // 加密；`decode_stored_value` 在读取时按相反顺序解密再解引用
+        let encrypted_owned = self.encryption_key.map(|key| encrypt_updates(updates, &key));
+        let encrypted_borrowed = encrypted_owned.as_ref().map(|owned| owned_to_borrowed(owned));
+        let updates: &[Update] = encrypted_borrowed.as_deref().unwrap_or(updates);
+
         // 构建 RawUpdate 数组
         // 注意：我们需要保持所有嵌套更新的生命周期
         let mut all_nested: Vec<Vec<ffi::RawUpdate>> = Vec::new();
         let mut raw_updates: Vec<ffi::RawUpdate> = Vec::new();
-        
+
         for update in updates {
             // 递归构建嵌套更新
             let nested_raw = build_nested_raw(&update.nested, version as i64, &mut all_nested);
@@ -464,76 +2912,970 @@ impl Db {
             )?
         };
         
+        self.version_notify.notify_waiters();
+
+        let node = Node { inner };
+        if !self.is_on_disk() {
+            self.memory_roots.insert(version, node.clone());
+            for update in updates {
+                self.approx_memory_bytes += update.value.map_or(0, <[u8]>::len);
+            }
+            self.enforce_memory_limits(version);
+        }
+
+        if let Some(bloom) = &mut self.bloom_index {
+            for update in updates {
+                bloom.filter.insert(update.key);
+            }
+            if let Err(err) = std::fs::write(&bloom.path, bloom.filter.serialize()) {
+                tracing::warn!("failed to persist bloom index to {}: {err}", bloom.path);
+            }
+        }
+
+        Ok(node)
+    }
+
+    /// 用 [`DbConfig::with_bloom_index_path`] 启用的旁路索引快速判断 `key`
+    /// 是否*可能*在这个 db 里出现过（不区分版本——见该字段文档）
+    ///
+    /// `false` 是确定的：`key` 从来没有被写过。`true` 只代表"可能写过"，
+    /// 可能是误报，调用方仍然需要真正的 [`Db::find`] 来确认。没有启用
+    /// bloom 索引（[`DbConfig::with_bloom_index_path`] 没设置）时，这里没有
+    /// 任何信息可以用来排除 `key`，所以保守地返回 `true`（"不能排除，当作
+    /// 可能存在"），而不是谎称"一定不存在"。
+    pub fn contains_key_fast(&self, key: &[u8]) -> bool {
+        match &self.bloom_index {
+            Some(bloom) => bloom.filter.contains(key),
+            None => true,
+        }
+    }
+
+    /// [`Db::upsert_with_root`]，同时返回这次调用本身造成的差异
+    ///
+    /// # 当前限制
+    /// 见 [`DiffEntry`] 文档：这个裁剪后的代码树没有 `Db::diff`/`db_diff`，
+    /// 没有"重新扫一遍两个版本比较出差异"这条路，更没有请求里设想的
+    /// `db_upsert_with_diff` 这个 FFI。好在这次调用造成的差异，定义上就是
+    /// 刚刚应用的 `updates` 本身——不需要再扫一遍树，直接把传入的
+    /// `updates` 逐条映射成 `DiffEntry` 就是准确的，而且天然没有 TOCTOU
+    /// 问题（没有另一个写者能在这两步之间插进来，因为这本来就是同一步）。
+    /// 嵌套更新（存储 trie）不会被展开进返回的 `diff`，和 `DiffEntry` 本身
+    /// 只描述单层 key/value 的设定一致。
+    pub fn upsert_return_diff<'a>(
+        &mut self,
+        root: Option<&Node>,
+        updates: &'a [Update<'a>],
+        version: u64,
+    ) -> Result<(Node, Vec<DiffEntry<'a>>), Error> {
+        let new_root = self.upsert_with_root(root, updates, version)?;
+        let diff = updates
+            .iter()
+            .map(|update| DiffEntry { key: update.key, value: update.value })
+            .collect();
+        Ok((new_root, diff))
+    }
+
+    /// 按照多层 `path`（例如 `[account_key, storage_slot_key]`）写入一个
+    /// 值，自动搭好中间层的嵌套 [`Update`] 结构
+    ///
+    /// `path` 的最后一段才是真正写值的 key，前面每一段只是"通道"：按照
+    /// `depend/monad/category/mpt/update.hpp` 里 `Update` 的三态编码
+    /// （`value` 为空但 `next` 非空表示"只更新嵌套 trie，不改自身的
+    /// 值"），中间层复用 [`Update::delete`] 构造出 `value = None` 的节点
+    /// 再用 [`Update::with_nested`] 往上挂，不会抹掉中间层本来已经写过
+    /// 的自身值。`path.len() == 1` 时就是一次普通的 [`Db::upsert_with_root`]。
+    ///
+    /// # Panics
+    /// `path` 为空
+    pub fn insert_at_path(
+        &mut self,
+        root: Option<&Node>,
+        path: &[&[u8]],
+        value: &[u8],
+        version: u64,
+    ) -> Result<Node, Error> {
+        assert!(!path.is_empty(), "insert_at_path: path 不能为空");
+        let update = build_path_update(path, value);
+        Ok(self.upsert_with_root(root, &[update], version)?)
+    }
+
+    /// 按照多层 `path` 查找值，和 [`Db::insert_at_path`] 是一对
+    ///
+    /// 嵌套存储 trie 和账户自身共享同一棵 trie（见 [`Db::get_storage_trie_root`]
+    /// 的文档），`path` 各段的 nibbles 在树里就是依次首尾相接的，所以这里
+    /// 直接把 `path` 按顺序拼成一个完整 key，委托给 [`Db::find`]，不需要
+    /// 专门的嵌套查找原语。
+    pub fn find_at_path(&self, path: &[&[u8]], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        let full_key: Vec<u8> = path.iter().copied().flatten().copied().collect();
+        self.find(&full_key, version)
+    }
+
+    /// 依次把 `tx_updates` 里每一笔交易的更新应用到同一个版本号上，前一笔
+    /// 交易算出来的根作为下一笔的起点，返回每一笔交易各自的中间根
+    ///
+    /// 对应区块处理里"按顺序应用每笔交易的状态 diff，每笔都要算出自己的
+    /// 状态根（用于收据里的字段）"这个场景——省得调用方自己写
+    /// `for tx in tx_updates { root = db.upsert_with_root(root.as_ref(), tx, version)?; }`
+    /// 这段循环。所有交易共享同一个 `version`：这个引擎的版本粒度是整个
+    /// 区块，不是单笔交易，中间根只是同一个版本号下、同一棵树在应用过程
+    /// 中的不同快照，不是各自独立的版本。
+    pub fn replay_block(
+        &mut self,
+        starting_root: Option<&Node>,
+        tx_updates: &[&[Update]],
+        version: u64,
+    ) -> Result<Vec<Node>, Error> {
+        let mut root = starting_root.cloned();
+        let mut roots = Vec::with_capacity(tx_updates.len());
+        for updates in tx_updates {
+            let new_root = self.upsert_with_root(root.as_ref(), updates, version)?;
+            roots.push(new_root.clone());
+            root = Some(new_root);
+        }
+        Ok(roots)
+    }
+
+    /// 创建一个 [`WriteBatch`]，用来在一次 `commit` 里提交多个 `put`/
+    /// `delete`，不用调用方自己攒 `Vec<Update>`
+    ///
+    /// `root` 字段会立即初始化成当前 `latest_version()` 的根（还没有任何
+    /// 版本时是 `None`），所以空 batch `commit` 出来是当前状态原样不变，
+    /// 非空 batch 是在当前状态之上增量更新——和直接调用
+    /// `upsert_with_root(Some(&db.load_root(db.latest_version())?), ...)`
+    /// 是同一个效果。返回的 `WriteBatch<'a>` 不借用 `self`，`commit` 时才
+    /// 需要再传一次 `&mut Db`。
+    pub fn write_batch<'a>(&mut self, version: u64) -> WriteBatch<'a> {
+        let latest = self.latest_version();
+        let root = if latest != u64::MAX {
+            self.load_root(latest).ok()
+        } else {
+            None
+        };
+        WriteBatch {
+            updates: Vec::new(),
+            version,
+            root,
+        }
+    }
+
+    /// [`Db::upsert`] 的 `OwnedUpdate` 版本，方便在 `async` 代码里先把更新
+    /// 攒到一个 `Vec<OwnedUpdate>` 里（跨 `.await` point 存活），再一次性
+    /// 提交
+    pub fn upsert_owned(&mut self, updates: &[OwnedUpdate], version: u64) -> Result<Node, cxx::Exception> {
+        self.upsert_with_root_owned(None, updates, version)
+    }
+
+    /// [`Db::upsert_with_root`] 的 `OwnedUpdate` 版本，见 [`Db::upsert_owned`]
+    pub fn upsert_with_root_owned(
+        &mut self,
+        root: Option<&Node>,
+        updates: &[OwnedUpdate],
+        version: u64,
+    ) -> Result<Node, cxx::Exception> {
+        let borrowed: Vec<Update<'_>> = updates.iter().map(OwnedUpdate::as_borrowed).collect();
+        self.upsert_with_root(root, &borrowed, version)
+    }
+
+    /// 把一组已经算好的差异记录应用到（这个）DB 上
+    ///
+    /// 每条 [`DiffEntry`] 被转换成 `Update::put`（有值）或 `Update::delete`
+    /// （值为 `None`），然后一次性 `upsert` 到 `root` 之上，生成目标版本。
+    /// 常见用法是把另一个 DB 两个版本之间的差异"搬运"过来，而不必重放
+    /// 整个更新历史。
+    ///
+    /// # 参数
+    /// - `root`: 当前根节点（None 表示从空树开始）
+    /// - `entries`: 差异记录列表
+    /// - `version`: 应用之后的目标版本号
+    pub fn apply_diff(
+        &mut self,
+        root: Option<&Node>,
+        entries: &[DiffEntry],
+        version: u64,
+    ) -> Result<Node, cxx::Exception> {
+        let updates: Vec<Update> = entries.iter().map(Update::from_diff_entry).collect();
+        self.upsert_with_root(root, &updates, version)
+    }
+
+    /// 条件更新：只有当 `condition_key` 在 `root` 上的当前值等于
+    /// `expected_value`（`None` 表示期望该 key 不存在）时才执行
+    /// `updates`，否则返回 [`Error::ConditionFailed`] 且不修改 DB
+    ///
+    /// 这是一个乐观并发控制原语：条件检查和 upsert 在 C++ 侧原子地完成，
+    /// 不存在"先 find 后 upsert"之间被其他写入者插入一次更新的竞态窗口。
+    ///
+    /// # 注意
+    /// 条件检查比较的是 trie 中存储的原始字节，不经过
+    /// `DbConfig::with_value_dedup` / `with_encryption_key` 的编解码；开启
+    /// 这些选项时 `expected_value` 需要是编码后的字节。
+    pub fn upsert_conditional(
+        &mut self,
+        root: Option<&Node>,
+        condition_key: &[u8],
+        expected_value: Option<&[u8]>,
+        updates: &[Update],
+        version: u64,
+    ) -> Result<Node, Error> {
+        let mut all_nested: Vec<Vec<ffi::RawUpdate>> = Vec::new();
+        let mut raw_updates: Vec<ffi::RawUpdate> = Vec::new();
+        for update in updates {
+            let nested_raw = build_nested_raw(&update.nested, version as i64, &mut all_nested);
+            raw_updates.push(update.to_raw(version as i64, nested_raw));
+        }
+
+        let root_ptr = root.map_or(ptr::null(), |r| &*r.inner as *const _);
+        let (expected_ptr, expected_len) = expected_value
+            .map_or((ptr::null(), 0), |v| (v.as_ptr(), v.len()));
+
+        let mut condition_met = false;
+        let inner = unsafe {
+            ffi::db_upsert_conditional(
+                self.inner.pin_mut(),
+                root_ptr,
+                condition_key,
+                expected_ptr,
+                expected_len,
+                expected_value.is_some(),
+                raw_updates.as_ptr(),
+                raw_updates.len(),
+                version,
+                &mut condition_met,
+            )?
+        };
+
+        if !condition_met {
+            return Err(Error::ConditionFailed);
+        }
+
+        self.version_notify.notify_waiters();
+
+        Ok(Node { inner })
+    }
+
+    /// 返回一个共享的 [`tokio::sync::Notify`]，每次成功的 `upsert`（任意 root）
+    /// 都会调用它的 `notify_waiters()`
+    ///
+    /// 适合只关心"版本变了"而不需要完整 `VersionEvent` 数据的调用方：
+    /// `db.watch_version_advance().notified().await` 即可在循环里等待下一次写入。
+    pub fn watch_version_advance(&self) -> Arc<tokio::sync::Notify> {
+        self.version_notify.clone()
+    }
+
+    /// 原子地返回当前 `latest_version` 和上面的 notify 句柄，避免先读版本号
+    /// 再拿句柄之间出现 TOCTOU：如果在两次调用之间发生了一次 upsert，单独调用
+    /// `latest_version()` 再 `watch_version_advance()` 可能会错过那次通知
+    pub fn current_version_notify(&self) -> (u64, Arc<tokio::sync::Notify>) {
+        (self.latest_version(), self.version_notify.clone())
+    }
+
+    /// 返回一个 [`DiffStream`]，持续产出每个新版本的差异
+    ///
+    /// 见 `DiffStream`/`DiffIter` 文档里说明的限制：这个裁剪后的代码树没有
+    /// `db_diff`，所以每次产出的 `DiffIter` 目前都是空的，`diff_stream` 能
+    /// 如实告诉调用方"版本变到了哪个号"，给不出具体改动的 key。
+    pub fn diff_stream(&self) -> DiffStream<'_> {
+        DiffStream::new(self)
+    }
+
+    /// 订阅 prune 事件：每次 [`Db::update_finalized_version`] /
+    /// [`Db::rewind_to_version`] 触发了一次 prune（`earliest_version()`
+    /// 前进），订阅者都会收到一条带有被回收版本号的 [`PruningEvent`]
+    ///
+    /// C++ 侧没有逐版本的 prune 回调，这里是通过比较触发前后的
+    /// `earliest_version()` 推算出来的，所以 `pruned_versions` 对应的是
+    /// `[old_earliest, new_earliest)` 这个区间。
+    pub fn subscribe_pruning(&mut self) -> PruningSubscription {
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        self.pruning_senders.push(sender);
+        PruningSubscription { receiver }
+    }
+
+    /// 给所有 prune 订阅者广播一次事件，并顺手清掉已经被丢弃的订阅者
+    ///
+    /// 用 `try_send` 而不是 `send`：广播发生在持有 `&mut self` 的同步代码
+    /// 路径里，不能 `.await`；channel 满了就直接丢弃这次事件（订阅者消费
+    /// 太慢自己负责追）,而不是阻塞调用方的 upsert/rewind。
+    fn notify_pruned(&mut self, old_earliest: u64, new_earliest: u64) {
+        if new_earliest <= old_earliest {
+            return;
+        }
+        let event = PruningEvent { pruned_versions: (old_earliest..new_earliest).collect() };
+        self.pruning_senders.retain(|sender| {
+            !matches!(
+                sender.try_send(event.clone()),
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_))
+            )
+        });
+    }
+
+    /// 合并两个独立的 trie 根，生成一个新的根节点
+    ///
+    /// 常见于状态同步场景：将下载得到的 account trie 根和本地的 storage
+    /// trie 根合并为一棵树。冲突 key（两棵树中都存在但值不同）按 `policy`
+    /// 处理。
+    ///
+    /// # 参数
+    /// - `a` / `a_version`: 要合并的第一个根节点，及它实际所属的版本
+    /// - `b` / `b_version`: 要合并的第二个根节点，及它实际所属的版本
+    /// - `version`: 合并结果写入的目标版本号——和 `a_version`/`b_version`
+    ///   是三个独立的数字；`a`、`b` 常常来自不同的同步/写入时间点（比如
+    ///   一个是刚同步下来的 account trie 根，一个是更早写入的本地
+    ///   storage trie 根），不能假设它们和目标版本相同
+    /// - `policy`: 冲突处理策略
+    pub fn merge_roots(
+        &mut self,
+        a: &Node,
+        a_version: u64,
+        b: &Node,
+        b_version: u64,
+        version: u64,
+        policy: MergeConflict,
+    ) -> Result<Node, Error> {
+        let inner = unsafe {
+            ffi::db_merge_roots(
+                self.inner.pin_mut(),
+                &*a.inner as *const _,
+                a_version,
+                &*b.inner as *const _,
+                b_version,
+                version,
+                policy as u8,
+            )?
+        };
+
         Ok(Node { inner })
     }
-    
-    /// 预加载节点到缓存（仅 RW 磁盘模式）
-    /// 
-    /// 遍历根节点下的所有可缓存节点，将它们加载到内存中。
-    /// 这可以加速后续的读取操作。
-    /// 
-    /// # 返回
-    /// 加载的节点数量
-    /// 
-    /// # 注意
-    /// - 仅在 RW 磁盘模式下有效
-    /// - 内存模式和只读模式返回 0
-    pub fn prefetch(&mut self, root: &Node) -> usize {
-        ffi::db_prefetch(self.inner.pin_mut(), &root.inner)
+
+    /// 把另一个 [`Db`]（甚至已经被 drop 的那个）产出的 `node` 真正搬进
+    /// `self` 的节点存储，返回一个在 `self` 里全新建立的根节点
+    ///
+    /// 和 [`Node::clone`] 不一样：`Node::clone` 只是多克隆一份指向原始
+    /// 节点数据的指针，原始 [`Db`] 析构之后那份数据是否还能用取决于它是
+    /// 内存模式还是磁盘模式；`copy_node` 内部复用 [`Db::merge_roots`] 同一套
+    /// 收集 (key, value) 再重新 `upsert` 的机制，把数据实际写进 `self`，
+    /// 不依赖 `node` 原来所在的那个 `Db` 是否还活着。
+    ///
+    /// # 限制
+    /// 收集 `node` 子树下的 (key, value) 用的是 `self`（目标 db）的
+    /// `traverse`，不是 `node` 原来所在的那个 `Db`——如果 `node` 来自一个
+    /// 磁盘模式的 `Db`，且它的子树里有还没加载进内存的节点（需要按磁盘
+    /// 偏移量读取），这里会尝试用 `self` 的 I/O 去读取那些偏移量，而
+    /// `self` 和 `node` 原来所在的 `Db` 是两个独立的磁盘文件，这样读出来
+    /// 的结果没有意义。目前只保证 `node` 子树已经完全驻留在内存里（比如
+    /// `node` 来自一个内存模式的 `Db`，或者调用前已经把它完整遍历过一遍）
+    /// 时是正确的。
+    pub fn copy_node(&mut self, node: &Node, version: u64) -> Result<Node, Error> {
+        let inner = ffi::db_adopt_node(self.inner.pin_mut(), &*node.inner, version)?;
+        Ok(Node { inner })
+    }
+
+    /// 并行批量导入：按 `key[0] % shard_count` 把 `iter` 分片，每个分片在
+    /// 独立线程里各自开一个临时内存 [`Db`] 构建子树，再用
+    /// [`Db::merge_roots`] 依次合并进返回的 `Db` 里
+    ///
+    /// # 与请求描述的差异
+    /// 没有引入 `rayon`：这个 crate 现有的并发原语（`AsyncFifo` worker、
+    /// `DbConfig::with_memory_trim_interval` 背后的 `TrimThread`）都是直接
+    /// 用 `std::thread`，没有哪个模块依赖 rayon，为了这一个方法单独加一个
+    /// 线程池依赖没有足够的理由；`std::thread::scope` 已经能满足"每个
+    /// shard 一个线程并行构建"的需求。也没有新增单独的 benchmark（见
+    /// `Cargo.toml`：这个 crate 目前没有 `[[bench]]` 条目或 criterion 依赖，
+    /// 加一套全新的 benchmark 基础设施超出这次改动范围）。
+    ///
+    /// # 当前限制
+    /// [`Db::merge_roots`] 现有的测试都只合并同一个 `Db` 实例上的两个根；
+    /// 这里依赖它的文档字面意思（"合并两个独立的 trie 根"，没有要求
+    /// 来自同一个 `Db`）把它用在跨 `Db` 实例的场景——底层 `collect_leaves`
+    /// 只是在给定的根节点上做纯内存遍历，不依赖发起 `Db` 自己的存储状态，
+    /// 所以这应该是安全的，但这个具体用法（shard 产出的根喂给另一个 `Db`
+    /// 的 `merge_roots`）目前没有专门的现有测试覆盖过。
+    pub fn from_iterator_parallel<I>(
+        iter: I,
+        version: u64,
+        shard_count: usize,
+        merge_version: u64,
+    ) -> Result<(Db, Node), Error>
+    where
+        I: Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    {
+        let shard_count = shard_count.max(1);
+        let mut shards: Vec<Vec<(Vec<u8>, Vec<u8>)>> = vec![Vec::new(); shard_count];
+        for (key, value) in iter {
+            let shard = key.first().copied().unwrap_or(0) as usize % shard_count;
+            shards[shard].push((key, value));
+        }
+
+        let shard_results: Vec<Result<(Db, Node), Error>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = shards
+                .into_iter()
+                .map(|batch| {
+                    scope.spawn(move || -> Result<(Db, Node), Error> {
+                        let mut shard_db = Db::open_memory()?;
+                        let updates: Vec<Update> =
+                            batch.iter().map(|(k, v)| Update::put(k, v)).collect();
+                        let root = shard_db.upsert(&updates, version)?;
+                        Ok((shard_db, root))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("shard builder thread panicked"))
+                .collect()
+        });
+
+        let mut shard_results = shard_results.into_iter().collect::<Result<Vec<_>, Error>>()?;
+
+        let (mut merged_db, mut merged_root) = shard_results.remove(0);
+        // `merged_root` 刚从 shard 里拿出来时是在 `version` 下构建的；从第
+        // 一次合并开始，它就变成了 `merge_roots` 写入的 `merge_version`。
+        let mut merged_root_version = version;
+        for (_shard_db, shard_root) in shard_results {
+            merged_root = merged_db.merge_roots(
+                &merged_root,
+                merged_root_version,
+                &shard_root,
+                version,
+                merge_version,
+                MergeConflict::TakeB,
+            )?;
+            merged_root_version = merge_version;
+        }
+
+        Ok((merged_db, merged_root))
+    }
+
+    /// 在一个显式持有的根节点下查找 key
+    ///
+    /// 与 [`Db::find`] 不同，这里不依赖引擎按 version 记录的根——内存模式
+    /// 下 upsert 不会写入该记录，[`Db::merge_roots`] 返回的根同样如此。
+    /// 适用于这些场景下对已知根节点做点查。
+    pub fn find_in_root(
+        &self,
+        root: &Node,
+        key: &[u8],
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, cxx::Exception> {
+        let node =
+            unsafe { ffi::db_find_in_root(&self.inner, &*root.inner as *const _, key, version)? };
+
+        if !ffi::node_has_value(&node) {
+            return Ok(None);
+        }
+
+        let len = ffi::node_value_len(&node);
+        if len == 0 {
+            return Ok(Some(Vec::new()));
+        }
+
+        let mut buf = vec![0u8; len];
+        let copied = ffi::node_copy_value(&node, &mut buf);
+        buf.truncate(copied);
+
+        Ok(Some(self.decode_stored_value(buf)))
+    }
+
+    /// 对 `roots` 里的每个根节点分别查找 `key`，按 `roots` 的顺序返回结果
+    ///
+    /// 请求里提到的批量 FFI（一次调用传入整个根节点数组，返回一个打包好的
+    /// `MultiRootResultHandle`）在这个引擎里没有对应的批处理入口——
+    /// `db_find_in_root` 本身已经是单次查找的完整路径，C++ 侧没有能在一次
+    /// 调用里对多个根复用查找状态的内部接口可以复用。这里就是对已有的
+    /// [`Db::find_in_root`] 按顺序逐个调用，好处是不需要新增、维护一个只
+    /// 为这一个场景存在的批处理 FFI 类型。
+    pub fn find_in_roots(
+        &self,
+        key: &[u8],
+        roots: &[&Node],
+        version: u64,
+    ) -> Result<Vec<Option<Vec<u8>>>, cxx::Exception> {
+        roots.iter().map(|root| self.find_in_root(root, key, version)).collect()
+    }
+
+    /// 把从引擎读到的原始字节还原成调用方写入时传入的 value
+    ///
+    /// 逆序撤销 `upsert_with_root` 在写入路径上做的变换：先尝试解密（若启用
+    /// 了加密且存在魔数前缀），再尝试去重解引用（若启用了去重且长度像一个
+    /// 哈希）。两者都没命中时原样返回。
+    fn decode_stored_value(&self, buf: Vec<u8>) -> Vec<u8> {
+        let buf = match &self.encryption_key {
+            Some(key) => decrypt_value(key, &buf).unwrap_or(buf),
+            None => buf,
+        };
+
+        if self.value_dedup {
+            if let Ok(hash) = <[u8; 32]>::try_from(buf.as_slice()) {
+                if let Some(value) = self.dedup_store.get(&hash) {
+                    return value.clone();
+                }
+            }
+        }
+
+        buf
+    }
+
+    /// 预加载节点到缓存（仅 RW 磁盘模式）
+    /// 
+    /// 遍历根节点下的所有可缓存节点，将它们加载到内存中。
+    /// 这可以加速后续的读取操作。
+    /// 
+    /// # 返回
+    /// 加载的节点数量
+    /// 
+    /// # 注意
+    /// - 仅在 RW 磁盘模式下有效
+    /// - 内存模式和只读模式返回 0
+    pub fn prefetch(&mut self, root: &Node) -> usize {
+        ffi::db_prefetch(self.inner.pin_mut(), &root.inner)
+    }
+    
+    /// 检查数据库是否只读
+    pub fn is_read_only(&self) -> bool {
+        ffi::db_is_read_only(&self.inner)
+    }
+    
+    /// 获取数据库统计信息
+    pub fn stats(&self) -> DbStats {
+        let mut latest_version = 0u64;
+        let mut earliest_version = 0u64;
+        let mut history_length = 0u64;
+        let mut is_on_disk = false;
+        let mut is_read_only = false;
+        let mut finalized_version = 0u64;
+
+        unsafe {
+            ffi::db_get_stats(
+                &self.inner,
+                &mut latest_version,
+                &mut earliest_version,
+                &mut history_length,
+                &mut is_on_disk,
+                &mut is_read_only,
+                &mut finalized_version,
+            );
+        }
+
+        DbStats {
+            latest_version,
+            earliest_version,
+            history_length,
+            is_on_disk,
+            is_read_only,
+            finalized_version,
+            wal_path: self.wal_path.clone(),
+            estimated_disk_size_bytes: self.estimated_disk_size(),
+        }
+    }
+
+    /// 估算当前数据库占用的磁盘/内存空间（字节）
+    ///
+    /// # 当前限制
+    /// `depend/monad/category/mpt/db.hpp` 里的 `mpt::Db` 没有暴露任何
+    /// "查询后端存储引擎大小估计值" 的接口，所以这里没有新增一个虚构的
+    /// `ffi::db_estimate_disk_size`。磁盘模式下是真实、可用的实现：
+    /// 递归遍历 `db_path`（含 WAL 子目录/`.schema_version` sidecar 等），
+    /// 用 `std::fs::metadata` 把每个文件的大小加起来——这就是
+    /// `du -sh <db_path>` 会报告的同一个数字。内存模式没有文件可以
+    /// `stat`，这里退化成 [`Db::memory_usage`] 的 `heap_bytes`（只统计
+    /// Rust 侧的 value 去重表，不含 C++ 引擎自身的堆内存，和
+    /// `memory_usage` 文档里说明的限制一致）。
+    pub fn estimated_disk_size(&self) -> u64 {
+        match &self.db_path {
+            Some(path) => directory_size_bytes(std::path::Path::new(path)),
+            None => self.memory_usage().heap_bytes as u64,
+        }
+    }
+
+    /// 估算 value 去重表占用的堆内存
+    ///
+    /// # 当前限制
+    /// 这里只统计 [`DbConfig::with_value_dedup`] 维护的 Rust 侧去重表，
+    /// 不包含 C++ 引擎本身的内存占用（引擎没有暴露细粒度的内存统计接口）。
+    /// 未启用去重时该值恒为 0。
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let heap_bytes = self
+            .dedup_store
+            .iter()
+            .map(|(_, value)| std::mem::size_of::<[u8; 32]>() + value.capacity())
+            .sum();
+
+        MemoryUsage { heap_bytes }
+    }
+
+    /// [`DbConfig::with_memory_compaction_threshold`] 用来判断是否超限的运行计数器
+    /// （[`DbConfig::with_max_memory_versions`] 数的是版本个数，不用它）
+    ///
+    /// 只在内存模式下累加（磁盘模式恒为 0），每次 [`Db::upsert_with_root`]
+    /// 成功后加上这次写入所有 `Update::value` 的字节数；这是单调递增的
+    /// "历史写入总量"，不是 [`Db::memory_roots`] 当前实际缓存占用的精确
+    /// 值——丢弃旧版本（被 [`Db::prune_before_version`] 或者触发阈值后的
+    /// 自动裁剪）不会让它变小，和 [`Db::memory_usage`] 统计的去重表堆内存
+    /// 是两个互不重叠的数字。
+    pub fn approximate_memory_bytes(&self) -> usize {
+        self.approx_memory_bytes
+    }
+
+    /// 将 value 去重表序列化为 JSON，便于持久化/重新加载
+    #[cfg(feature = "serde")]
+    pub fn dedup_store_to_json(&self) -> String {
+        let entries: std::collections::HashMap<String, String> = self
+            .dedup_store
+            .iter()
+            .map(|(hash, value)| (to_hex(hash), to_hex(value)))
+            .collect();
+        serde_json::to_string(&entries).expect("dedup store serialization cannot fail")
+    }
+
+    /// 从 `dedup_store_to_json` 产生的 JSON 恢复去重表
+    #[cfg(feature = "serde")]
+    pub fn load_dedup_store_from_json(&mut self, json: &str) -> Result<(), serde_json::Error> {
+        let entries: std::collections::HashMap<String, String> = serde_json::from_str(json)?;
+        for (hash_hex, value_hex) in entries {
+            let hash_bytes = from_hex(&hash_hex).map_err(serde::de::Error::custom)?;
+            let hash: [u8; 32] = hash_bytes.try_into().map_err(|_| {
+                serde::de::Error::custom("dedup store key must be exactly 32 bytes")
+            })?;
+            self.dedup_store.insert(hash, from_hex(&value_hex).map_err(serde::de::Error::custom)?);
+        }
+        Ok(())
+    }
+}
+
+/// `Db::memory_usage` 的返回值
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryUsage {
+    /// 估算的堆内存占用（字节）
+    pub heap_bytes: usize,
+}
+
+/// 数据库统计信息
+#[derive(Debug, Clone)]
+pub struct DbStats {
+    /// 最新版本号
+    pub latest_version: u64,
+    /// 最早版本号（仅磁盘模式有效）
+    pub earliest_version: u64,
+    /// 历史保留长度
+    pub history_length: u64,
+    /// 是否磁盘模式
+    pub is_on_disk: bool,
+    /// 是否只读
+    pub is_read_only: bool,
+    /// Finalized 版本（`u64::MAX` 表示未设置）
+    pub finalized_version: u64,
+    /// 打开时指定的 WAL 路径（见 `DbConfig::with_wal_path`）
+    pub wal_path: Option<String>,
+    /// 见 [`Db::estimated_disk_size`]
+    pub estimated_disk_size_bytes: u64,
+}
+
+impl DbStats {
+    /// 当前保留的版本总数
+    pub fn total_versions(&self) -> u64 {
+        self.latest_version - self.earliest_version + 1
+    }
+
+    /// 和更早一次采样的快照 `older` 比较，得到两次采样之间的增量
+    ///
+    /// 监控系统常用这个来算速率，比如
+    /// `inserts_per_second = diff.version_delta as f64 / elapsed.as_secs_f64()`
+    pub fn diff(&self, older: &DbStats) -> DbStatsDelta {
+        DbStatsDelta {
+            version_delta: self.latest_version as i64 - older.latest_version as i64,
+            finalized_version_delta: self.finalized_version as i64
+                - older.finalized_version as i64,
+        }
+    }
+
+    /// 校验 `latest_version` 相对 `older` 没有倒退
+    ///
+    /// 正常情况下 `latest_version` 只会前进；如果两次采样之间发生了
+    /// `rewind_to_version`，或者调用方把新旧快照传反了，这里会如实报错，
+    /// 而不是让 [`DbStats::diff`] 悄悄给出一个负的 `version_delta`。
+    pub fn assert_monotone(&self, older: &DbStats) -> Result<(), Error> {
+        if self.latest_version < older.latest_version {
+            return Err(Error::NotMonotonic {
+                older: older.latest_version,
+                current: self.latest_version,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Sub<DbStats> for DbStats {
+    type Output = DbStatsDelta;
+
+    fn sub(self, older: DbStats) -> DbStatsDelta {
+        self.diff(&older)
+    }
+}
+
+/// [`DbStats::diff`] 两次采样之间的增量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbStatsDelta {
+    pub version_delta: i64,
+    pub finalized_version_delta: i64,
+}
+
+/// [`Db::read_at`] 返回的 cursor：固定住一个 `version`，让一串读操作不用
+/// 每次都重复传
+pub struct ReadCursor<'a> {
+    db: &'a mut Db,
+    version: u64,
+}
+
+impl<'a> ReadCursor<'a> {
+    /// 先 [`Db::load_root`] 拿到这个 cursor 固定版本的根，再
+    /// [`Db::find_in_root`] 点查——不直接调 [`Db::find`]，因为那个依赖引擎
+    /// 按 version 记录的根，内存模式下 upsert 不写这份记录（见
+    /// `Db::find_in_root` 文档），查旧版本会不准；`load_root` 走的是
+    /// [`Db::memory_roots`] 缓存，这个问题不存在。
+    pub fn find(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let root = self.db.load_root(self.version)?;
+        Ok(self.db.find_in_root(&root, key, self.version)?)
+    }
+
+    /// 批量 [`ReadCursor::find`]，根节点只加载一次
+    pub fn find_batch(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let root = self.db.load_root(self.version)?;
+        keys.iter()
+            .map(|key| Ok(self.db.find_in_root(&root, key, self.version)?))
+            .collect()
+    }
+
+    /// `key` 在这个 cursor 固定的版本下是否存在
+    pub fn has_key(&self, key: &[u8]) -> Result<bool, Error> {
+        Ok(self.find(key)?.is_some())
+    }
+
+    /// 按 `prefix` 扫描这个 cursor 固定版本下的所有 (key, value)，内部复用
+    /// [`ScanIter`]（和 [`Db::delete_prefix`] 同一套基于 `AsyncFifo` 的分页
+    /// 遍历机制）
+    ///
+    /// # 限制
+    /// 和 [`ReadCursor::find`] 不一样，`AsyncFifo::submit_traverse` 没有
+    /// "显式传入根节点" 的重载，只能按 `version` 让引擎自己查——如果这个
+    /// cursor 固定的 `version` 不是 `latest_version()`，内存模式下同样会
+    /// 撞上 [`ReadCursor::find`] 文档里提到的 write_root=false 限制。
+    pub fn scan(&mut self, prefix: &[u8]) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>, Error> {
+        let fifo = self.db.create_async_fifo().map_err(|_| {
+            Error::Unsupported("read_at: failed to create AsyncFifo for scan")
+        })?;
+        Ok(ScanIter::with_prefix(fifo, self.version, prefix.to_vec()))
+    }
+}
+
+/// [`Db::copy_to`]、[`Db::delete_prefix`]、[`ReadCursor::scan`] 等方法内部用的
+/// 扫描迭代器：按 [`AsyncFifo::submit_traverse_from`] 的分页机制顺序扫出某个
+/// 版本下的全部 (key, value) 条目
+///
+/// 类型本身不是 `pub`，只以 `impl Iterator` 的形式从这些方法返回；真要暴露
+/// 的话应该长得更像 [`AsyncFifo::traverse_stream`]。
+struct ScanIter {
+    fifo: AsyncFifo,
+    version: u64,
+    prefix: Vec<u8>,
+    after_key: Option<Vec<u8>>,
+    page: std::collections::VecDeque<(Vec<u8>, Vec<u8>)>,
+    done: bool,
+}
+
+impl ScanIter {
+    const PAGE_SIZE: u32 = 1000;
+
+    fn new(fifo: AsyncFifo, version: u64) -> Self {
+        Self::with_prefix(fifo, version, Vec::new())
+    }
+
+    /// 和 [`ScanIter::new`] 一样，但只扫出 `prefix` 开头的那部分 key，见
+    /// [`Db::delete_prefix`]
+    fn with_prefix(fifo: AsyncFifo, version: u64, prefix: Vec<u8>) -> Self {
+        fifo.start(1);
+        Self {
+            fifo,
+            version,
+            prefix,
+            after_key: None,
+            page: std::collections::VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn fetch_next_page(&mut self) {
+        let submitted = match &self.after_key {
+            None => self.fifo.submit_traverse(&self.prefix, self.version, Self::PAGE_SIZE, 0),
+            Some(after_key) => {
+                self.fifo.submit_traverse_from(&self.prefix, self.version, after_key, Self::PAGE_SIZE, 0)
+            }
+        };
+        if !submitted {
+            self.done = true;
+            return;
+        }
+
+        let mut results = Vec::new();
+        loop {
+            match self.fifo.poll_traverse() {
+                Some(r) if r.status == ResultStatus::TraverseEnd => break,
+                Some(r) => results.push(r),
+                None => std::thread::sleep(Duration::from_micros(50)),
+            }
+        }
+
+        if results.is_empty() {
+            self.done = true;
+            return;
+        }
+        if results.len() < Self::PAGE_SIZE as usize {
+            self.done = true;
+        } else {
+            self.after_key = results.last().map(|r| r.merkle_hash.to_vec());
+        }
+
+        for r in results {
+            if let Some(value) = r.value {
+                self.page.push_back((r.merkle_hash.to_vec(), value));
+            }
+        }
+    }
+}
+
+impl Iterator for ScanIter {
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.page.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            self.fetch_next_page();
+        }
+    }
+}
+
+/// [`Db::traverse_subtrie`] 用的分页遍历器
+///
+/// 和 [`ScanIter`] 几乎一样，唯一的区别是深度边界处自身没有 value 的节点
+/// 也会被保留在页里（`value` 为 `None`），不会像 `ScanIter::fetch_next_page`
+/// 那样因为 `r.value` 是 `None` 就丢掉——所以这里没有直接复用 `ScanIter`。
+pub struct SubTrieTraversal {
+    fifo: AsyncFifo,
+    prefix: Vec<u8>,
+    max_depth: u32,
+    version: u64,
+    after_key: Option<Vec<u8>>,
+    page: std::collections::VecDeque<(Vec<u8>, Option<Vec<u8>>)>,
+    done: bool,
+}
+
+impl SubTrieTraversal {
+    const PAGE_SIZE: u32 = 1000;
+
+    fn new(fifo: AsyncFifo, prefix: Vec<u8>, max_depth: u32, version: u64) -> Self {
+        fifo.start(1);
+        Self {
+            fifo,
+            prefix,
+            max_depth,
+            version,
+            after_key: None,
+            page: std::collections::VecDeque::new(),
+            done: false,
+        }
     }
-    
-    /// 检查数据库是否只读
-    pub fn is_read_only(&self) -> bool {
-        ffi::db_is_read_only(&self.inner)
+
+    fn fetch_next_page(&mut self) {
+        let submitted = match &self.after_key {
+            None => self.fifo.submit_traverse_subtrie(
+                &self.prefix, self.version, self.max_depth, Self::PAGE_SIZE, 0,
+            ),
+            Some(after_key) => self.fifo.submit_traverse_subtrie_from(
+                &self.prefix, self.version, after_key, self.max_depth, Self::PAGE_SIZE, 0,
+            ),
+        };
+        if !submitted {
+            self.done = true;
+            return;
+        }
+
+        let mut results = Vec::new();
+        loop {
+            match self.fifo.poll_traverse() {
+                Some(r) if r.status == ResultStatus::TraverseEnd => break,
+                Some(r) => results.push(r),
+                None => std::thread::sleep(Duration::from_micros(50)),
+            }
+        }
+
+        if results.is_empty() {
+            self.done = true;
+            return;
+        }
+        if results.len() < Self::PAGE_SIZE as usize {
+            self.done = true;
+        } else {
+            self.after_key = results.last().map(|r| r.merkle_hash.to_vec());
+        }
+
+        for r in results {
+            self.page.push_back((r.merkle_hash.to_vec(), r.value));
+        }
     }
-    
-    /// 获取数据库统计信息
-    pub fn stats(&self) -> DbStats {
-        let mut latest_version = 0u64;
-        let mut earliest_version = 0u64;
-        let mut history_length = 0u64;
-        let mut is_on_disk = false;
-        let mut is_read_only = false;
-        let mut finalized_version = 0u64;
-        
-        unsafe {
-            ffi::db_get_stats(
-                &self.inner,
-                &mut latest_version,
-                &mut earliest_version,
-                &mut history_length,
-                &mut is_on_disk,
-                &mut is_read_only,
-                &mut finalized_version,
-            );
+}
+
+impl Iterator for SubTrieTraversal {
+    type Item = (Vec<u8>, Option<Vec<u8>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.page.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            self.fetch_next_page();
         }
-        
-        DbStats {
-            latest_version,
-            earliest_version,
-            history_length,
-            is_on_disk,
-            is_read_only,
-            finalized_version,
+    }
+}
+
+/// 递归累加 `path` 下所有文件的大小，见 [`Db::estimated_disk_size`]
+///
+/// 遇到读不到的路径（权限、中途被删除等）直接跳过，不中断统计——这里只是
+/// 给监控面板用的估算值，不是需要强一致性的账本。
+fn directory_size_bytes(path: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    let mut total = 0u64;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            total += directory_size_bytes(&entry.path());
+        } else {
+            total += metadata.len();
         }
     }
+    total
 }
 
-/// 数据库统计信息
-#[derive(Debug, Clone, Copy)]
-pub struct DbStats {
-    /// 最新版本号
-    pub latest_version: u64,
-    /// 最早版本号（仅磁盘模式有效）
-    pub earliest_version: u64,
-    /// 历史保留长度
-    pub history_length: u64,
-    /// 是否磁盘模式
-    pub is_on_disk: bool,
-    /// 是否只读
-    pub is_read_only: bool,
-    /// Finalized 版本（`u64::MAX` 表示未设置）
-    pub finalized_version: u64,
+/// 把一批 (key, value) 对通过一次 `upsert_with_root` 写进 `dst`，见
+/// [`Db::copy_to`]
+fn copy_batch_into(
+    dst: &mut Db,
+    root: Option<&Node>,
+    batch: &[(Vec<u8>, Vec<u8>)],
+    version: u64,
+) -> Result<Node, Error> {
+    let updates: Vec<Update> = batch.iter().map(|(k, v)| Update::put(k, v)).collect();
+    Ok(dst.upsert_with_root(root, &updates, version)?)
 }
 
 /// 递归构建嵌套 RawUpdate
@@ -557,6 +3899,143 @@ fn build_nested_raw<'a>(
     storage.last().unwrap()
 }
 
+/// 给 [`Db::insert_at_path`] 用：把 `path` 递归搭成一条嵌套 [`Update`]
+/// 链，只有最后一段真正带 `value`，中间段都是 `value = None` 的
+/// "通道"节点（[`Update::delete`] + [`Update::with_nested`]）
+fn build_path_update<'a>(path: &[&'a [u8]], value: &'a [u8]) -> Update<'a> {
+    match path {
+        [] => unreachable!("insert_at_path 已经校验过 path 非空"),
+        [last] => Update::put(last, value),
+        [head, rest @ ..] => Update::delete(head).with_nested(vec![build_path_update(rest, value)]),
+    }
+}
+
+/// 计算 keccak256 哈希
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    use tiny_keccak::{Hasher, Keccak};
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut out = [0u8; 32];
+    hasher.finalize(&mut out);
+    out
+}
+
+/// 持有期间占用一个 reader 名额，`Drop` 时自动释放，见 [`Db::find`]
+struct ReaderGuard<'a> {
+    inner: &'a ffi::DbHandle,
+}
+
+impl Drop for ReaderGuard<'_> {
+    fn drop(&mut self) {
+        ffi::db_release_reader(self.inner);
+    }
+}
+
+/// `Update` 的全拥有版本，用于在 value 去重/加密时脱离原始借用独立存在
+struct EncodedUpdate {
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+    nested: Vec<EncodedUpdate>,
+}
+
+/// 递归地把 `updates` 中的 value 替换成它们的 keccak256 哈希
+///
+/// 原始 value 会被记录进 `dedup_store`（已存在相同哈希时不会重复拷贝）
+fn dedup_updates(
+    updates: &[Update],
+    dedup_store: &mut std::collections::HashMap<[u8; 32], Vec<u8>>,
+) -> Vec<EncodedUpdate> {
+    updates
+        .iter()
+        .map(|update| EncodedUpdate {
+            key: update.key.to_vec(),
+            value: update.value.map(|value| {
+                let hash = keccak256(value);
+                dedup_store.entry(hash).or_insert_with(|| value.to_vec());
+                hash.to_vec()
+            }),
+            nested: dedup_updates(&update.nested, dedup_store),
+        })
+        .collect()
+}
+
+/// 把 `EncodedUpdate` 树借用回 `Update`，以便复用既有的 RawUpdate 构建逻辑
+fn owned_to_borrowed(owned: &[EncodedUpdate]) -> Vec<Update<'_>> {
+    owned
+        .iter()
+        .map(|update| Update {
+            key: &update.key,
+            value: update.value.as_deref(),
+            nested: owned_to_borrowed(&update.nested),
+        })
+        .collect()
+}
+
+/// 加密 value 的前缀字节，用来在 `find` 时和未加密的普通字节区分开
+const ENCRYPTION_MAGIC: u8 = 0xEE;
+
+/// 用 AES-256-GCM 加密一个 value，返回 `[magic][nonce(12 字节)][ciphertext]`
+///
+/// nonce 每次调用都重新随机生成（通过 `getrandom`），不依赖调用方传入
+fn encrypt_value(key: &EncryptionKey, plaintext: &[u8]) -> Vec<u8> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.0));
+
+    let mut nonce_bytes = [0u8; 12];
+    getrandom::getrandom(&mut nonce_bytes).expect("getrandom failed to produce a nonce");
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .expect("AES-256-GCM encryption cannot fail for in-memory buffers");
+
+    let mut out = Vec::with_capacity(1 + nonce_bytes.len() + ciphertext.len());
+    out.push(ENCRYPTION_MAGIC);
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// 解密 `encrypt_value` 产生的字节；前缀不是魔数或解密失败（例如 key 不对）
+/// 时返回 `None`
+fn decrypt_value(key: &EncryptionKey, bytes: &[u8]) -> Option<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    const HEADER_LEN: usize = 1 + 12;
+    if bytes.len() < HEADER_LEN || bytes[0] != ENCRYPTION_MAGIC {
+        return None;
+    }
+
+    let nonce = Nonce::from_slice(&bytes[1..HEADER_LEN]);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.0));
+    cipher.decrypt(nonce, &bytes[HEADER_LEN..]).ok()
+}
+
+/// 递归地把 `updates` 中的 value 替换成它们的 AES-256-GCM 密文
+fn encrypt_updates(updates: &[Update], key: &EncryptionKey) -> Vec<EncodedUpdate> {
+    updates
+        .iter()
+        .map(|update| EncodedUpdate {
+            key: update.key.to_vec(),
+            value: update.value.map(|value| encrypt_value(key, value)),
+            nested: encrypt_updates(&update.nested, key),
+        })
+        .collect()
+}
+
+/// [`Node::serialize`]/[`Node::deserialize`] 支持的编码格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeSerialFormat {
+    /// 自定义的紧凑二进制编码：nibble 路径 + value + 哈希，只有编码方向
+    /// 可用（见 [`Node::deserialize`] 文档）
+    Compact,
+    /// 节点自身的 RLP 编码，等价于 [`Node::to_rlp`]/[`Node::from_rlp`]
+    Rlp,
+}
+
 /// MPT 节点
 pub struct Node {
     inner: UniquePtr<ffi::NodeHandle>,
@@ -596,18 +4075,202 @@ impl Node {
         let mut buf = vec![0u8; len];
         let copied = ffi::node_copy_data(&self.inner, &mut buf);
         buf.truncate(copied);
-        
+
         buf
     }
-    
+
+    /// 获取 Merkle 数据的原始指针和长度，不分配/不拷贝
+    ///
+    /// # Safety
+    /// 返回的指针指向 `self` 内部持有的数据，只在 `self` 存活期间有效，
+    /// 调用方不能让指针的生命周期超出 `self`；也不能对返回的内存写入——
+    /// C++ 那一侧把它当成只读数据。大多数调用方应该用更安全的
+    /// [`Node::with_data`]，只有在确实需要跨越这个 API 的所有权边界时
+    /// （比如交给一段手写的 C FFI）才直接用这个。
+    pub unsafe fn data_ptr(&self) -> (*const u8, usize) {
+        (ffi::node_data_ptr(&self.inner), ffi::node_data_len(&self.inner))
+    }
+
+    /// 不分配内存地访问节点的 Merkle 数据
+    ///
+    /// [`Node::data`] 每次调用都要 `Vec<u8>` 分配 + 拷贝；只需要读一次
+    /// （比如算哈希或者比较）的调用方可以用这个借用接口避免那次分配。
+    /// 内部基于 [`Node::data_ptr`]，但这层包装保证了切片的生命周期不会
+    /// 超出这次调用。
+    pub fn with_data<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        let len = ffi::node_data_len(&self.inner);
+        if len == 0 {
+            return f(&[]);
+        }
+        let ptr = ffi::node_data_ptr(&self.inner);
+        let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
+        f(slice)
+    }
+
+    /// 节点自身压缩边的 nibble 路径，按字节打包（奇数个 nibble 时最后半字节
+    /// 补 0），每个 `u8` 的高 4 位和低 4 位各是一个 nibble
+    ///
+    /// 注意这不是从树根到该节点的完整路径——`mpt::Node` 本身不持有祖先分支
+    /// 信息，只知道从它的直接父节点分支到它自己这一段压缩边。对于只有一个
+    /// key 的浅树（没有发生分支），这一段就等于完整的 key；一旦发生分支，
+    /// 调用方需要自行在遍历过程中累积路径（参考 `db_merge_roots` 内部的
+    /// `CollectLeavesMachine` 实现）才能得到完整 key。
+    pub fn nibble_path(&self) -> Vec<u8> {
+        let len = ffi::node_nibble_path_len(&self.inner);
+        let bytes = (len + 1) / 2;
+        if bytes == 0 {
+            return Vec::new();
+        }
+        let mut buf = vec![0u8; bytes];
+        let copied = ffi::node_copy_nibble_path(&self.inner, &mut buf);
+        buf.truncate(copied);
+        buf
+    }
+
+    /// 节点自身压缩边对应的 key 前缀字节（见 [`Node::nibble_path`] 的限制：
+    /// 只在没有发生分支、该节点就是唯一叶子的浅树下等于完整 key）
+    pub fn key_prefix(&self) -> Vec<u8> {
+        self.nibble_path()
+    }
+
     /// 计算节点的 Merkle 根哈希（32 字节 Keccak256）
-    /// 
+    ///
     /// 这是以太坊风格的状态根哈希，可以与区块头中的 stateRoot 比对。
     pub fn root_hash(&self) -> [u8; 32] {
         let mut hash = [0u8; 32];
         ffi::node_compute_root_hash(&self.inner, &mut hash);
         hash
     }
+
+    /// 交叉检查节点内部的值和 Merkle 数据是否一致
+    ///
+    /// # 当前限制
+    /// 不管是从数据库里正常 `load_root`/`find` 出来的树节点，还是
+    /// [`Node::from_rlp`] 构造出来的分离节点，[`Node::root_hash`] 暴露出来
+    /// 的哈希都是"现算现用"——没有另外一份独立存储、可能和当前数据不一致
+    /// 的"缓存哈希"字段可以拿来交叉校验（引擎没有暴露这样的字段）。所以
+    /// 这里检测不出"字节被篡改但仍然是一段合法字节序列"的情况，只能检查
+    /// 节点本身是否有效：分离节点原始字节是否非空（[`Node::from_rlp`] 对
+    /// 空切片也会"成功"返回一个句柄，但不代表任何真实节点），树节点重新
+    /// 计算哈希是否成功。
+    pub fn verify_consistency(&self) -> bool {
+        ffi::node_verify_consistency(&self.inner)
+    }
+
+    /// 将节点导出为 RLP 编码
+    ///
+    /// 当前等价于 `data()`：节点的 Merkle 数据本身就是以太坊 MPT 的 RLP 编码。
+    pub fn to_rlp(&self) -> Vec<u8> {
+        self.data()
+    }
+
+    /// 从一段 RLP 字节重建一个与数据库分离的节点
+    ///
+    /// 返回的节点只支持 `data()`/`to_rlp()`/`root_hash()`，不能参与
+    /// `Db::upsert` 等需要完整 trie 结构的操作。用于节点间同步场景，
+    /// 接收方拿到对端发来的 RLP 字节后即可校验哈希，无需落库。
+    pub fn from_rlp(rlp: &[u8]) -> Result<Node, cxx::Exception> {
+        let inner = ffi::node_from_rlp_alloc(rlp)?;
+        Ok(Node { inner })
+    }
+
+    /// 按 `format` 把节点编码成一段可以跨网络传输的字节序列
+    ///
+    /// `NodeSerialFormat::Rlp` 就是 [`Node::to_rlp`]。`NodeSerialFormat::Compact`
+    /// 见该枚举文档里的限制：只有 encode 方向是真正可用的。
+    pub fn serialize(&self, format: NodeSerialFormat) -> Vec<u8> {
+        match format {
+            NodeSerialFormat::Rlp => self.to_rlp(),
+            NodeSerialFormat::Compact => {
+                let nibble_path = self.nibble_path();
+                let value = self.value();
+                let hash = self.root_hash();
+
+                let mut buf = Vec::new();
+                buf.extend_from_slice(&(nibble_path.len() as u32).to_le_bytes());
+                buf.extend_from_slice(&nibble_path);
+                match &value {
+                    Some(v) => {
+                        buf.push(1);
+                        buf.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                        buf.extend_from_slice(v);
+                    }
+                    None => buf.push(0),
+                }
+                buf.extend_from_slice(&hash);
+                buf
+            }
+        }
+    }
+
+    /// [`Node::serialize`] 的反方向
+    ///
+    /// # 当前限制
+    /// `NodeSerialFormat::Rlp` 直接转发给 [`Node::from_rlp`]，是真正能用的
+    /// 完整往返。`NodeSerialFormat::Compact` 没有对应的反方向：
+    /// `bridge.cpp`/`bridge.hpp` 里唯一能"凭空"构造出一个
+    /// `NodeHandle`（不依赖已打开的 `Db`）的函数是 `node_from_rlp_alloc`，
+    /// 它在 C++ 侧调的是 `mpt::Node` 专门为 RLP 反序列化写的构造路径；
+    /// `mpt::Node`（见 `depend/monad/category/mpt/node.hpp`）没有另一个
+    /// "从 nibble 路径 + value + hash 这几个裸字段重建"的构造函数，凑不出
+    /// 一个能在 Rust 侧安全持有的 `NodeHandle`。所以 `Compact` 方向这里
+    /// 总是返回 `Err(Error::Unsupported(..))`，`serialize(Compact)` 编码出来
+    /// 的字节目前只能给人/其它语言的实现去解析，不能喂回这个 binding。
+    pub fn deserialize(bytes: &[u8], format: NodeSerialFormat) -> Result<Node, Error> {
+        match format {
+            NodeSerialFormat::Rlp => Ok(Node::from_rlp(bytes)?),
+            NodeSerialFormat::Compact => {
+                Err(Error::Unsupported("Node::deserialize(Compact): no NodeHandle constructor for the compact format exists in bridge.cpp"))
+            }
+        }
+    }
+
+    /// 遍历 `self` 的子节点：branch 节点每个非空分支的 `(nibble, 子节点哈希)`
+    ///
+    /// # 未实现
+    /// 和 [`Db::dump_trie_to_graphviz`] 文档里说的是同一个限制：
+    /// `bridge.hpp`/`bridge.cpp` 里现有的节点相关函数（`node_has_value`/
+    /// `node_value_len`/`node_copy_value`/`node_data_len`/`node_copy_data`/
+    /// `node_nibble_path_len`/`node_copy_nibble_path`/`node_compute_root_hash`/
+    /// `node_to_rlp`）全都是"读这一个节点自己的信息"，没有一个把子节点暴露
+    /// 出来。底层 `mpt::Node`（见 `depend/monad/category/mpt/node.hpp` 的
+    /// `mask`/`child_data`）确实按 branch index 存了每个子节点的数据，但还
+    /// 没有对应的 FFI 函数把它们搬到 Rust 这一侧，所以这里总是返回一个空
+    /// 迭代器，不代表这个节点真的没有子节点。
+    pub fn iter_children(&self) -> ChildIter<'_> {
+        ChildIter { _node: self }
+    }
+
+    /// [`Node::iter_children`] 的"顺便把子节点加载成 [`Node`]"版本
+    ///
+    /// 除了 `iter_children` 本身的限制之外，即便将来能拿到子节点哈希，
+    /// [`Db::get_node_by_hash`] 目前也总是返回 `Error::Unsupported`（这个
+    /// 引擎没有哈希到节点的全局索引）——两层限制叠在一起，这里直接返回
+    /// 空 `Vec`，不去调用 `get_node_by_hash`。
+    pub fn children_with_nodes(&self, db: &Db, version: u64) -> Result<Vec<(u8, Node)>, Error> {
+        let _ = (db, version);
+        Ok(Vec::new())
+    }
+}
+
+/// [`Node::iter_children`] 返回的迭代器；见该方法文档里的限制说明——目前
+/// 永远为空
+pub struct ChildIter<'a> {
+    _node: &'a Node,
+}
+
+impl<'a> Iterator for ChildIter<'a> {
+    type Item = (u8, [u8; 32]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        None
+    }
+}
+
+impl<'a> ExactSizeIterator for ChildIter<'a> {
+    fn len(&self) -> usize {
+        0
+    }
 }
 
 impl Clone for Node {
@@ -618,6 +4281,149 @@ impl Clone for Node {
     }
 }
 
+/// 对 [`Node`] 的零开销只读借用
+///
+/// `Node::clone()` 会触发一次 `ffi::node_clone`（C++ 堆分配）；读多写少的
+/// 代码如果只需要 `root_hash`/`has_value`/`value`/`data`/`value_len` 这些
+/// 只读方法，没必要为此克隆整个节点。[`Db::find_ref`] 之类从
+/// `UniquePtr` 借出 `NodeRef` 的接口会因为所有权问题而不安全，所以目前
+/// 只有 [`Node::as_ref`] 这一个安全的构造方式。
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    inner: &'a ffi::NodeHandle,
+}
+
+impl<'a> NodeRef<'a> {
+    /// 节点是否有值
+    pub fn has_value(&self) -> bool {
+        ffi::node_has_value(self.inner)
+    }
+
+    /// 值的长度，不拷贝内容
+    pub fn value_len(&self) -> usize {
+        ffi::node_value_len(self.inner)
+    }
+
+    /// 获取节点的值
+    pub fn value(&self) -> Option<Vec<u8>> {
+        if !self.has_value() {
+            return None;
+        }
+
+        let len = self.value_len();
+        if len == 0 {
+            return Some(Vec::new());
+        }
+
+        let mut buf = vec![0u8; len];
+        let copied = ffi::node_copy_value(self.inner, &mut buf);
+        buf.truncate(copied);
+
+        Some(buf)
+    }
+
+    /// 获取节点的 Merkle 数据（用于生成 proof）
+    pub fn data(&self) -> Vec<u8> {
+        let len = ffi::node_data_len(self.inner);
+        if len == 0 {
+            return Vec::new();
+        }
+
+        let mut buf = vec![0u8; len];
+        let copied = ffi::node_copy_data(self.inner, &mut buf);
+        buf.truncate(copied);
+
+        buf
+    }
+
+    /// 计算节点的 Merkle 根哈希（32 字节 Keccak256）
+    pub fn root_hash(&self) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        ffi::node_compute_root_hash(self.inner, &mut hash);
+        hash
+    }
+
+    /// 见 [`Node::verify_consistency`]
+    pub fn verify_consistency(&self) -> bool {
+        ffi::node_verify_consistency(self.inner)
+    }
+}
+
+impl Node {
+    /// 借出一个零开销的 [`NodeRef`]，不触发 `ffi::node_clone`
+    pub fn as_ref(&self) -> NodeRef<'_> {
+        NodeRef { inner: &*self.inner }
+    }
+}
+
+/// 一批预分配的 NodeHandle，配合 [`Db::find_with_pool`] 复用，避免热路径
+/// 读取时反复触发 `new NodeHandle`
+pub struct NodeHandlePool {
+    pool: Vec<UniquePtr<ffi::NodeHandle>>,
+}
+
+impl NodeHandlePool {
+    /// 预分配 `capacity` 个空的 NodeHandle
+    pub fn new(capacity: usize) -> Result<Self, Error> {
+        let mut pool = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            pool.push(ffi::node_alloc()?);
+        }
+        Ok(Self { pool })
+    }
+
+    /// 取出一个 NodeHandle；池子空了就临时分配一个新的
+    fn acquire(&mut self) -> Result<UniquePtr<ffi::NodeHandle>, Error> {
+        match self.pool.pop() {
+            Some(handle) => Ok(handle),
+            None => Ok(ffi::node_alloc()?),
+        }
+    }
+
+    /// 把一个用完的 NodeHandle 重置后放回池子
+    fn release(&mut self, mut handle: UniquePtr<ffi::NodeHandle>) {
+        ffi::node_reset(handle.pin_mut());
+        self.pool.push(handle);
+    }
+}
+
+/// [`Db::find_with_pool`] 返回的值，借用 [`NodeHandlePool`] 里的一个
+/// NodeHandle；drop 时自动把 NodeHandle 归还给池子
+pub struct PooledValue<'a> {
+    pool: &'a mut NodeHandlePool,
+    handle: Option<UniquePtr<ffi::NodeHandle>>,
+}
+
+impl PooledValue<'_> {
+    /// 获取对应的值
+    pub fn value(&self) -> Option<Vec<u8>> {
+        let handle = self.handle.as_ref().expect("handle taken before drop");
+
+        if !ffi::node_has_value(handle) {
+            return None;
+        }
+
+        let len = ffi::node_value_len(handle);
+        if len == 0 {
+            return Some(Vec::new());
+        }
+
+        let mut buf = vec![0u8; len];
+        let copied = ffi::node_copy_value(handle, &mut buf);
+        buf.truncate(copied);
+
+        Some(buf)
+    }
+}
+
+impl Drop for PooledValue<'_> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.pool.release(handle);
+        }
+    }
+}
+
 // 测试需要在单独的集成测试中运行，因为静态库链接顺序问题
 // TODO: 添加集成测试
 // #[cfg(test)]
@@ -0,0 +1,475 @@
+//! Merkle Proof - 证明节点的表示与 RLP 编码
+//!
+//! `Proof` 持有从 `Db::get_proof` 取得的一组证明节点（以太坊风格的
+//! Merkle Patricia Trie 证明由 root 到 leaf 的一串节点编码组成）。
+
+use crate::{ffi, Db, Error};
+
+/// Key 在某个版本下的 Merkle 证明
+///
+/// # 当前限制
+/// 底层引擎目前只暴露目标节点本身，尚不支持返回完整的 root -> leaf 路径，
+/// 因此 `nodes` 目前只包含一个元素（目标节点的 Merkle 编码）。后续随着
+/// `Node::nibble_path` 等底层能力补全，会扩展为完整路径。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Proof {
+    /// 按 root -> leaf 顺序排列的证明节点
+    pub nodes: Vec<Vec<u8>>,
+}
+
+impl Proof {
+    /// 解析 `db_get_proof_raw` 返回的拼接缓冲区
+    ///
+    /// 每个节点前有一个 4 字节小端长度前缀
+    fn from_raw(mut raw: &[u8]) -> Self {
+        let mut nodes = Vec::new();
+        while raw.len() >= 4 {
+            let len = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize;
+            raw = &raw[4..];
+            if raw.len() < len {
+                break;
+            }
+            nodes.push(raw[..len].to_vec());
+            raw = &raw[len..];
+        }
+        Self { nodes }
+    }
+}
+
+impl Proof {
+    /// 校验这份证明的目标节点确实编码了 `expected_value`
+    ///
+    /// # 当前限制
+    /// 见结构体文档：`nodes` 目前只有目标节点自身的编码，没有从 `root` 到
+    /// 它的完整路径，所以这里没办法真正沿路径把 hash 核对到 `root`——只能
+    /// 验证目标节点自身解码出来的 value 和 `expected_value` 一致。`root`
+    /// 参数先保留下来，等 `nodes` 扩展成完整路径之后才会真正用上它。
+    pub fn verify(&self, root: [u8; 32], expected_value: Option<&[u8]>) -> bool {
+        let _ = root;
+        match self.nodes.first() {
+            Some(encoded) => match crate::Node::from_rlp(encoded) {
+                Ok(node) => node.value().as_deref() == expected_value,
+                Err(_) => false,
+            },
+            None => expected_value.is_none(),
+        }
+    }
+}
+
+/// [`Db::get_proof_with_witness`] 的返回值：每个 key 各自的证明，外加它们
+/// 共用的 witness 节点集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofWithWitness {
+    /// 和传入的 `keys` 按顺序一一对应
+    pub proofs: Vec<Proof>,
+    /// 所有 `proofs` 里出现过的证明节点的并集（按字节内容去重）
+    pub witness_nodes: Vec<Vec<u8>>,
+}
+
+impl Db {
+    /// 生成 key 在指定版本下的 Merkle 证明
+    pub fn get_proof(&self, key: &[u8], version: u64) -> Result<Proof, Error> {
+        let raw = ffi::db_get_proof_raw(&self.inner, key, version)?;
+        Ok(Proof::from_raw(&raw))
+    }
+
+    /// 一次性获取多个 key 的 Merkle 证明，外加它们共享的 witness 节点集合
+    ///
+    /// # 当前限制
+    /// 理想情况下应该是一个新的 `ffi::db_get_proof_with_witness`，在 C++
+    /// 侧一次遍历里顺带收集所有被访问到的节点；但 `bridge.hpp`/`bridge.cpp`
+    /// 目前只有单 key 的 `db_get_proof_raw`，没有对应的多 key/带 witness
+    /// 版本。这里改为对每个 key 分别调用 [`Db::get_proof`]，`witness_nodes`
+    /// 取所有证明节点的并集（按字节内容去重）——语义上仍然满足"witness 是
+    /// 所有单个证明节点集合的超集"，只是没有 C++ 那一侧"一次遍历"的性能
+    /// 优势。
+    pub fn get_proof_with_witness(
+        &self,
+        keys: &[&[u8]],
+        version: u64,
+    ) -> Result<ProofWithWitness, Error> {
+        let mut proofs = Vec::with_capacity(keys.len());
+        let mut witness_nodes: Vec<Vec<u8>> = Vec::new();
+        for key in keys {
+            let proof = self.get_proof(key, version)?;
+            for node in &proof.nodes {
+                if !witness_nodes.contains(node) {
+                    witness_nodes.push(node.clone());
+                }
+            }
+            proofs.push(proof);
+        }
+        Ok(ProofWithWitness { proofs, witness_nodes })
+    }
+
+    /// 生成 `key` 在指定版本下确实不存在的证明
+    ///
+    /// # 当前限制
+    /// 和 [`Proof`] 一样（见其文档），底层裁剪后的 `mpt::Db` 只暴露 `find`
+    /// 本身，没有暴露"走到 key 路径最深处的那个节点"或者"trie 里最近的
+    /// 邻居 key"这些遍历能力——真正的以太坊风格不存在证明需要这些信息
+    /// 才能构造出一组可以脱离 DB、只靠 `root_hash` 独立验证的证明节点。
+    /// 这里没办法虚构一个 `ffi::db_prove_non_existence`，只能复用已有的
+    /// `Db::find` 确认"这一刻确实查不到"，`proof_nodes` 和 `closest_key`
+    /// 永远是空/`None`，[`ExclusionProof::verify`] 也因此只能做最弱的自洽
+    /// 检查，不是真正不依赖 DB 的离线验证。
+    pub fn prove_non_existence(&self, key: &[u8], version: u64) -> Result<ExclusionProof, Error> {
+        if self.find(key, version)?.is_some() {
+            return Err(Error::Unsupported(
+                "prove_non_existence: key exists at this version, cannot build an exclusion proof",
+            ));
+        }
+        Ok(ExclusionProof {
+            key: key.to_vec(),
+            proof_nodes: Vec::new(),
+            closest_key: None,
+        })
+    }
+
+    /// 找出 `key` 在 `version` 下遍历过程中经过的节点，用于调试"为什么这个
+    /// key 查不到"
+    ///
+    /// # 当前限制
+    /// 同上面几个方法的限制：底层引擎目前只暴露目标节点本身，没有暴露
+    /// root -> leaf 这条路径上的中间节点——没有"取某个分支下标对应子节点"
+    /// 的 FFI 原语（见 [`Db::dump_trie_to_graphviz`] 的文档）。这里复用
+    /// [`Db::get_proof`] 拿到目标节点的 RLP 编码：找到时 `nodes` 里只有
+    /// 它自己这一个 [`TriePathNode`]（`node_type` 固定是 `Leaf`），没找到
+    /// 时 `nodes` 为空。`NodeType::Root`/`Branch`/`Extension` 这三种分类
+    /// 目前永远不会被构造——保留它们只是为了和请求里列的分类对齐，等
+    /// 底层补上"取子节点"的能力后才有机会真正用上。
+    pub fn get_trie_path(&self, key: &[u8], version: u64) -> Result<TriePath, Error> {
+        let proof = self.get_proof(key, version)?;
+        match proof.nodes.first() {
+            Some(encoded) => {
+                let node = crate::Node::from_rlp(encoded)?;
+                let nibble = node.nibble_path().first().copied().unwrap_or(0);
+                let hash = node.root_hash();
+                Ok(TriePath {
+                    nodes: vec![TriePathNode { node_type: NodeType::Leaf, nibble, hash }],
+                    found: true,
+                })
+            }
+            None => Ok(TriePath { nodes: Vec::new(), found: false }),
+        }
+    }
+}
+
+/// [`Db::get_trie_path`] 里单个节点的分类
+///
+/// # 当前限制
+/// 见 [`Db::get_trie_path`] 的文档：目前只有 `Leaf` 会被真正构造出来
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeType {
+    Root,
+    Branch,
+    Extension,
+    Leaf,
+    Missing,
+}
+
+/// [`Db::get_trie_path`] 路径上的一个节点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriePathNode {
+    pub node_type: NodeType,
+    /// 这个节点对应的 nibble（见 `Db::get_trie_path` 的限制：目前总是从
+    /// `Node::nibble_path` 的第一个半字节取，不是真正"父节点分支下标"的
+    /// 那个语义）
+    pub nibble: u8,
+    pub hash: [u8; 32],
+}
+
+/// [`Db::get_trie_path`] 的返回值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriePath {
+    pub nodes: Vec<TriePathNode>,
+    pub found: bool,
+}
+
+/// [`Db::prove_non_existence`] 的返回值：证明某个 key 在指定版本下确实
+/// 不在 trie 里
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExclusionProof {
+    /// 被证明不存在的 key
+    pub key: Vec<u8>,
+    /// 见 `Db::prove_non_existence` 的限制：目前永远是空的
+    pub proof_nodes: Vec<Vec<u8>>,
+    /// 见 `Db::prove_non_existence` 的限制：目前永远是 `None`
+    pub closest_key: Option<Vec<u8>>,
+}
+
+impl ExclusionProof {
+    /// 校验这份证明
+    ///
+    /// 见 `Db::prove_non_existence` 的限制：`proof_nodes` 目前总是空的，
+    /// 没办法独立于 DB 验证，这里只能做最弱的自洽检查——一份诚实的
+    /// `ExclusionProof` 不应该带着非空的 `proof_nodes` 或者 `closest_key`。
+    pub fn verify(&self, _root_hash: [u8; 32]) -> bool {
+        self.proof_nodes.is_empty() && self.closest_key.is_none()
+    }
+}
+
+/// 将 `Proof` 编码为以太坊轻客户端使用的 RLP 证明格式（节点列表）
+pub struct ProofEncoder;
+
+impl ProofEncoder {
+    /// 将证明节点编码为一个 RLP list
+    pub fn encode(proof: &Proof) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for node in &proof.nodes {
+            encode_rlp_bytes(node, &mut payload);
+        }
+        let mut out = Vec::new();
+        encode_rlp_list_header(payload.len(), &mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+}
+
+/// 将 RLP 编码的证明节点列表解码回 `Vec<Vec<u8>>`
+pub struct ProofDecoder;
+
+impl ProofDecoder {
+    pub fn decode(bytes: &[u8]) -> Result<Vec<Vec<u8>>, RlpError> {
+        let (items, rest) = decode_rlp_list(bytes)?;
+        if !rest.is_empty() {
+            return Err(RlpError::TrailingBytes);
+        }
+        Ok(items)
+    }
+}
+
+/// 最小化的 RLP 解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RlpError {
+    UnexpectedEof,
+    NotAList,
+    TrailingBytes,
+}
+
+impl std::fmt::Display for RlpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RlpError::UnexpectedEof => write!(f, "unexpected end of RLP input"),
+            RlpError::NotAList => write!(f, "expected an RLP list"),
+            RlpError::TrailingBytes => write!(f, "trailing bytes after RLP list"),
+        }
+    }
+}
+
+impl std::error::Error for RlpError {}
+
+pub(crate) fn encode_rlp_bytes(data: &[u8], out: &mut Vec<u8>) {
+    if data.len() == 1 && data[0] < 0x80 {
+        out.push(data[0]);
+        return;
+    }
+    encode_len_header(0x80, 0xb7, data.len(), out);
+    out.extend_from_slice(data);
+}
+
+pub(crate) fn encode_rlp_list_header(payload_len: usize, out: &mut Vec<u8>) {
+    encode_len_header(0xc0, 0xf7, payload_len, out);
+}
+
+fn encode_len_header(short_base: u8, long_base: u8, len: usize, out: &mut Vec<u8>) {
+    if len <= 55 {
+        out.push(short_base + len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let first_nonzero = len_bytes.iter().position(|&b| b != 0).unwrap_or(len_bytes.len() - 1);
+        let trimmed = &len_bytes[first_nonzero..];
+        out.push(long_base + trimmed.len() as u8);
+        out.extend_from_slice(trimmed);
+    }
+}
+
+fn decode_rlp_list(bytes: &[u8]) -> Result<(Vec<Vec<u8>>, &[u8]), RlpError> {
+    let (payload, rest) = decode_rlp_header(bytes, true)?;
+    let mut items = Vec::new();
+    let mut remaining = payload;
+    while !remaining.is_empty() {
+        let (item, rest) = decode_rlp_header(remaining, false)?;
+        items.push(item.to_vec());
+        remaining = rest;
+    }
+    Ok((items, rest))
+}
+
+/// 解析一个 RLP 元素的头部，返回 (payload, 元素之后剩余的字节)
+fn decode_rlp_header(bytes: &[u8], expect_list: bool) -> Result<(&[u8], &[u8]), RlpError> {
+    let &first = bytes.first().ok_or(RlpError::UnexpectedEof)?;
+    let rest = &bytes[1..];
+
+    let (len, body, after_len): (usize, &[u8], &[u8]) = if first < 0x80 {
+        if expect_list {
+            return Err(RlpError::NotAList);
+        }
+        return Ok((&bytes[..1], rest));
+    } else if first <= 0xb7 {
+        if expect_list {
+            return Err(RlpError::NotAList);
+        }
+        let len = (first - 0x80) as usize;
+        (len, rest, rest)
+    } else if first <= 0xbf {
+        if expect_list {
+            return Err(RlpError::NotAList);
+        }
+        let len_of_len = (first - 0xb7) as usize;
+        let (len_bytes, after) = split_at_checked(rest, len_of_len)?;
+        (be_bytes_to_usize(len_bytes), after, after)
+    } else if first <= 0xf7 {
+        if !expect_list {
+            return Err(RlpError::NotAList);
+        }
+        let len = (first - 0xc0) as usize;
+        (len, rest, rest)
+    } else {
+        if !expect_list {
+            return Err(RlpError::NotAList);
+        }
+        let len_of_len = (first - 0xf7) as usize;
+        let (len_bytes, after) = split_at_checked(rest, len_of_len)?;
+        (be_bytes_to_usize(len_bytes), after, after)
+    };
+
+    let (payload, tail) = split_at_checked(body, len)?;
+    let _ = after_len;
+    Ok((payload, tail))
+}
+
+fn split_at_checked(bytes: &[u8], n: usize) -> Result<(&[u8], &[u8]), RlpError> {
+    if bytes.len() < n {
+        return Err(RlpError::UnexpectedEof);
+    }
+    Ok(bytes.split_at(n))
+}
+
+fn be_bytes_to_usize(bytes: &[u8]) -> usize {
+    let mut buf = [0u8; std::mem::size_of::<usize>()];
+    let start = buf.len().saturating_sub(bytes.len());
+    buf[start..].copy_from_slice(&bytes[bytes.len().saturating_sub(buf.len())..]);
+    usize::from_be_bytes(buf)
+}
+
+// ============================================================
+// serde - Ethereum JSON-RPC 兼容格式
+// ============================================================
+//
+// 以太坊 JSON-RPC（如 eth_getProof）期望证明节点表示为十六进制字符串数组：
+// ["0x...", "0x..."]
+
+#[cfg(feature = "serde")]
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + bytes.len() * 2);
+    s.push_str("0x");
+    for b in bytes {
+        s.push_str(&format!("{b:02x}"));
+    }
+    s
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() % 2 != 0 {
+        return Err(format!("odd-length hex string: {s}"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Proof {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.nodes.len()))?;
+        for node in &self.nodes {
+            seq.serialize_element(&to_hex(node))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Proof {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex_strings: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+        let nodes = hex_strings
+            .into_iter()
+            .map(|s| from_hex(&s).map_err(serde::de::Error::custom))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Proof { nodes })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Proof {
+    /// 序列化为以太坊 JSON-RPC 兼容的 JSON 字符串：`["0x...", ...]`
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Proof serialization cannot fail")
+    }
+
+    /// 从以太坊 JSON-RPC 兼容的 JSON 字符串解析
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_single_node_proof() {
+        let proof = Proof { nodes: vec![b"hello world, this node payload is definitely longer than 55 bytes so it exercises the long-form RLP length header".to_vec()] };
+        let encoded = ProofEncoder::encode(&proof);
+        let decoded = ProofDecoder::decode(&encoded).expect("decode failed");
+        assert_eq!(decoded, proof.nodes);
+    }
+
+    #[test]
+    fn round_trips_empty_proof() {
+        let proof = Proof::default();
+        let encoded = ProofEncoder::encode(&proof);
+        let decoded = ProofDecoder::decode(&encoded).expect("decode failed");
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn round_trips_short_strings() {
+        let proof = Proof { nodes: vec![b"abc".to_vec(), vec![], b"x".to_vec()] };
+        let encoded = ProofEncoder::encode(&proof);
+        let decoded = ProofDecoder::decode(&encoded).expect("decode failed");
+        assert_eq!(decoded, proof.nodes);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip_matches_eth_rpc_format() {
+        let proof = Proof { nodes: vec![vec![0xde, 0xad], vec![0xbe, 0xef]] };
+        let json = proof.to_json();
+        assert_eq!(json, r#"["0xdead","0xbeef"]"#);
+        let decoded = Proof::from_json(&json).expect("from_json failed");
+        assert_eq!(decoded, proof);
+    }
+
+    #[test]
+    fn exclusion_proof_with_empty_nodes_verifies() {
+        let proof = ExclusionProof { key: b"missing".to_vec(), proof_nodes: Vec::new(), closest_key: None };
+        assert!(proof.verify([0u8; 32]));
+    }
+
+    #[test]
+    fn exclusion_proof_with_a_closest_key_does_not_verify() {
+        let proof = ExclusionProof {
+            key: b"missing".to_vec(),
+            proof_nodes: Vec::new(),
+            closest_key: Some(b"neighbor".to_vec()),
+        };
+        assert!(!proof.verify([0u8; 32]));
+    }
+}
@@ -0,0 +1,207 @@
+//! 测试替身 - 不依赖 C++ 引擎即可跑测试
+//!
+//! `MockDb` 用纯 Rust 的 `BTreeMap` 模拟 trie 的 key/value/version 语义，
+//! 实现与 [`Db`] 相同的 [`DbInterface`]，方便在没有链接 C++ 二进制的环境下
+//! （例如纯逻辑单测、CI 的快速检查阶段）跑针对该接口的测试。
+
+use crate::{keccak256, Db, DbStats, Error, Update};
+use std::collections::BTreeMap;
+
+/// `Db` 和 `MockDb` 共享的最小接口
+///
+/// 只覆盖最常用的读写路径；`Db` 特有的能力（`merge_roots`、`fork_at_version`
+/// 等）不在这里，按需通过具体类型直接调用。
+pub trait DbInterface {
+    /// upsert 成功后返回的根，类型因实现而异（`Db` 返回真实的 trie 根节点，
+    /// `MockDb` 返回不持有任何 trie 状态的 [`MockNode`]）
+    type Node;
+
+    /// 查找 key 在指定版本下的值
+    fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error>;
+
+    /// 写入一批更新，返回新版本对应的根
+    fn upsert(&mut self, updates: &[Update], version: u64) -> Result<Self::Node, Error>;
+
+    /// 当前最新版本号
+    fn latest_version(&self) -> u64;
+
+    /// 数据库统计信息
+    fn stats(&self) -> DbStats;
+}
+
+impl DbInterface for Db {
+    type Node = crate::Node;
+
+    fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        Db::find(self, key, version).map_err(Error::from)
+    }
+
+    fn upsert(&mut self, updates: &[Update], version: u64) -> Result<Self::Node, Error> {
+        Db::upsert(self, updates, version).map_err(Error::from)
+    }
+
+    fn latest_version(&self) -> u64 {
+        Db::latest_version(self)
+    }
+
+    fn stats(&self) -> DbStats {
+        Db::stats(self)
+    }
+}
+
+/// `MockDb::upsert` 返回的占位根——`MockDb` 不维护真实的 trie 结构，
+/// 这里只记录写入时的版本号，供调用方保留句柄使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockNode {
+    version: u64,
+}
+
+impl MockNode {
+    /// 这个根对应的版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// 纯 Rust 实现的 `Db` 测试替身
+///
+/// 用 `key -> (version -> value)` 模拟 MPT 的版本化存储：查找时取
+/// `<= version` 的最近一次写入，None 表示该版本下 key 已被删除或从未写入。
+#[derive(Debug, Clone, Default)]
+pub struct MockDb {
+    data: BTreeMap<Vec<u8>, BTreeMap<u64, Option<Vec<u8>>>>,
+    latest_version: u64,
+}
+
+impl MockDb {
+    /// 创建一个空的 `MockDb`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 对 `version` 下所有仍存在的 (key, value) 按 key 排序后计算一个确定性哈希
+    ///
+    /// 不是真正的 Merkle 根——只是一个足以检测内容差异的摘要，用来在测试里
+    /// 断言 `MockDb` 与真实 `Db` 在同一组更新下得到"等价"的结果。
+    pub fn root_hash(&self, version: u64) -> [u8; 32] {
+        let mut buf = Vec::new();
+        for (key, versions) in &self.data {
+            let Some(Some(value)) = versions.range(..=version).next_back().map(|(_, v)| v.clone())
+            else {
+                continue;
+            };
+            buf.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buf.extend_from_slice(key);
+            buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&value);
+        }
+        keccak256(&buf)
+    }
+}
+
+impl DbInterface for MockDb {
+    type Node = MockNode;
+
+    fn find(&self, key: &[u8], version: u64) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .data
+            .get(key)
+            .and_then(|versions| versions.range(..=version).next_back())
+            .and_then(|(_, value)| value.clone()))
+    }
+
+    fn upsert(&mut self, updates: &[Update], version: u64) -> Result<MockNode, Error> {
+        for update in updates {
+            self.data
+                .entry(update.key.to_vec())
+                .or_default()
+                .insert(version, update.value.map(|v| v.to_vec()));
+        }
+        self.latest_version = self.latest_version.max(version);
+        Ok(MockNode { version })
+    }
+
+    fn latest_version(&self) -> u64 {
+        self.latest_version
+    }
+
+    fn stats(&self) -> DbStats {
+        DbStats {
+            latest_version: self.latest_version,
+            earliest_version: 0,
+            history_length: 0,
+            is_on_disk: false,
+            is_read_only: false,
+            finalized_version: 0,
+            wal_path: None,
+            estimated_disk_size_bytes: 0,
+        }
+    }
+}
+
+// 以下几个测试是 `tests/integration_test.rs` 中对应用例的一个子集，改用
+// `MockDb` 重新实现——验证这两个实现在基础读写语义上是一致的，同时不需要
+// 链接 C++ 引擎
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_then_find_returns_value() {
+        let mut db = MockDb::new();
+        let key = [1u8; 32];
+        db.upsert(&[Update::put(&key, b"value")], 1).expect("upsert failed");
+        assert_eq!(db.find(&key, 1).unwrap().as_deref(), Some(b"value".as_slice()));
+    }
+
+    #[test]
+    fn find_missing_key_returns_none() {
+        let db = MockDb::new();
+        assert_eq!(db.find(&[0u8; 32], 1).unwrap(), None);
+    }
+
+    #[test]
+    fn find_at_earlier_version_sees_earlier_value() {
+        let mut db = MockDb::new();
+        let key = [2u8; 32];
+        db.upsert(&[Update::put(&key, b"first")], 1).unwrap();
+        db.upsert(&[Update::put(&key, b"second")], 2).unwrap();
+
+        assert_eq!(db.find(&key, 1).unwrap().as_deref(), Some(b"first".as_slice()));
+        assert_eq!(db.find(&key, 2).unwrap().as_deref(), Some(b"second".as_slice()));
+    }
+
+    #[test]
+    fn delete_removes_value_from_that_version_onward() {
+        let mut db = MockDb::new();
+        let key = [3u8; 32];
+        db.upsert(&[Update::put(&key, b"value")], 1).unwrap();
+        db.upsert(&[Update::delete(&key)], 2).unwrap();
+
+        assert_eq!(db.find(&key, 1).unwrap().as_deref(), Some(b"value".as_slice()));
+        assert_eq!(db.find(&key, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn latest_version_tracks_highest_upserted_version() {
+        let mut db = MockDb::new();
+        let key = [4u8; 32];
+        db.upsert(&[Update::put(&key, b"a")], 1).unwrap();
+        db.upsert(&[Update::put(&key, b"b")], 5).unwrap();
+        assert_eq!(db.latest_version(), 5);
+    }
+
+    #[test]
+    fn root_hash_changes_when_content_changes_and_is_deterministic() {
+        let mut db = MockDb::new();
+        let key = [5u8; 32];
+
+        let empty_hash = db.root_hash(1);
+        db.upsert(&[Update::put(&key, b"value")], 1).unwrap();
+        let hash_a = db.root_hash(1);
+        let hash_b = db.root_hash(1);
+
+        assert_ne!(empty_hash, hash_a, "root hash should change after a write");
+        assert_eq!(hash_a, hash_b, "root_hash must be deterministic");
+    }
+}
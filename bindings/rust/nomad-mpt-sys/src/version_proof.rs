@@ -0,0 +1,63 @@
+//! 逐版本的根哈希链，见 [`Db::iter_version_proofs`]
+
+use crate::{Db, Error};
+
+/// 一个版本的根哈希以及它前一个版本的根哈希，见 [`VersionProof::verify_chain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionProof {
+    pub version: u64,
+    pub root_hash: [u8; 32],
+    /// `version - 1` 的根哈希；`version` 是整条链里的第一个版本（即
+    /// `version == 0`）时为 `None`
+    pub parent_root_hash: Option<[u8; 32]>,
+}
+
+impl VersionProof {
+    /// 检查 `self.parent_root_hash` 和 `prev` 的根哈希是否衔接得上
+    ///
+    /// `prev` 传 `None` 表示 `self` 应该是整条链的第一个版本
+    pub fn verify_chain(&self, prev: Option<&VersionProof>) -> bool {
+        self.parent_root_hash == prev.map(|p| p.root_hash)
+    }
+}
+
+/// [`Db::iter_version_proofs`] 返回的惰性迭代器
+///
+/// 和 [`Db::timeline`] 一样，底层 `mpt::Db` 没有"一次 C++ 调用批量返回多
+/// 个版本的根哈希"的接口——这里没有新增一个虚构的批量 FFI，每个版本的根
+/// 仍然各自一次 [`Db::load_root`]，只是用迭代器而不是 `timeline()` 那样
+/// 一次性 collect 成 `Vec`，这样调用方可以提前 `break` 而不用等整个区间
+/// 都加载完。
+pub struct VersionProofIter<'a> {
+    db: &'a Db,
+    next: u64,
+    to: u64,
+    prev_root_hash: Option<[u8; 32]>,
+}
+
+impl<'a> VersionProofIter<'a> {
+    pub(crate) fn new(db: &'a Db, from: u64, to: u64, prev_root_hash: Option<[u8; 32]>) -> Self {
+        Self { db, next: from, to, prev_root_hash }
+    }
+}
+
+impl<'a> Iterator for VersionProofIter<'a> {
+    type Item = Result<VersionProof, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next > self.to {
+            return None;
+        }
+        let version = self.next;
+        self.next += 1;
+
+        let root_hash = match self.db.load_root(version) {
+            Ok(node) => node.root_hash(),
+            Err(err) => return Some(Err(err.into())),
+        };
+
+        let parent_root_hash = self.prev_root_hash;
+        self.prev_root_hash = Some(root_hash);
+        Some(Ok(VersionProof { version, root_hash, parent_root_hash }))
+    }
+}
@@ -0,0 +1,69 @@
+//! Write-Ahead Log 回放 - 崩溃恢复场景下按版本重放历史更新
+//!
+//! `WriteAheadLog::open` 是设计上的入口：打开磁盘上的 WAL 文件，按版本顺序
+//! 产出 [`WalEntry`]。见 [`WriteAheadLog::open`] 的文档：当前这棵 trimmed
+//! 过的引擎树里并不存在独立的 WAL 文件格式，这里只保留接口形状。
+
+use crate::Error;
+
+/// 一条 WAL entry：某个版本写入的全部 key/value 更新
+pub struct WalEntry {
+    version: u64,
+    updates: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl WalEntry {
+    /// 这条 entry 对应的版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 这条 entry 里的更新数量
+    pub fn update_count(&self) -> usize {
+        self.updates.len()
+    }
+
+    /// 第 `i` 条更新的 key 和 value（`None` 表示删除）
+    pub fn update_at(&self, i: usize) -> (&[u8], Option<&[u8]>) {
+        let (key, value) = &self.updates[i];
+        (key.as_slice(), value.as_deref())
+    }
+}
+
+/// 按版本顺序回放 WAL 条目的迭代器，见 [`WriteAheadLog::open`]
+pub struct WalReader {
+    _private: (),
+}
+
+impl Iterator for WalReader {
+    type Item = Result<WalEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // `WriteAheadLog::open` 目前总是返回 `Err`，所以永远不会有实例
+        // 走到这里；保留空实现是为了让 `WalReader` 的形状（一个普通的
+        // `Iterator`）在引擎真的支持 WAL 之后不需要改调用方代码。
+        None
+    }
+}
+
+/// WAL（Write-Ahead Log）回放入口
+pub struct WriteAheadLog;
+
+impl WriteAheadLog {
+    /// 打开一个 WAL 文件用于崩溃恢复回放
+    ///
+    /// # 未实现
+    /// 底层 `mpt::Db`/`OnDiskDbConfig` 没有传统数据库意义上独立的 WAL 文件
+    /// 格式——见 [`crate::DbConfig::with_wal_path`] 的文档，那个路径实际上是
+    /// 第二个存储分片，并不是可以顺序回放的日志。这里没有字节流可以解析，
+    /// 所以总是返回 [`Error::Unsupported`]；`WalEntry`/`WalReader` 的接口
+    /// 形状保留下来，方便将来引擎侧加上真正的 WAL 之后直接对接，不需要
+    /// 调用方改代码。
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<WalReader, Error> {
+        let _ = path;
+        Err(Error::Unsupported(
+            "WriteAheadLog::open: this engine has no independent WAL file format to replay \
+             (see DbConfig::with_wal_path)",
+        ))
+    }
+}
@@ -0,0 +1,26 @@
+//! `wasm32` 目标下可用的纯 Rust、不依赖 FFI 的小工具
+//!
+//! # 当前限制
+//! `build.rs` 开头就有 `#[cfg(not(target_os = "linux"))] compile_error!(...)`：
+//! 这个 crate 整体要通过 cmake/cxx-build 把底层 C++ 引擎（`mpt::Db` 等）编译
+//! 进来，还依赖 `liburing`，这些在 wasm32 目标上都不存在。换句话说，在
+//! build.rs 真正支持"wasm32 下跳过 C++ 编译步骤"之前，`cargo build --target
+//! wasm32-unknown-unknown`/`wasm-pack build` 根本不会跑到 `lib.rs` 的 cfg 门
+//! 这一步——光在这里给 `Db`/`AsyncFifo` 补一圈
+//! `#[cfg(target_arch = "wasm32")] compile_error!(...)` 占位，并不能让 crate
+//! 真的在 wasm32 上构建成功，build.rs 会先失败。对应的 `wasm-pack build`
+//! CI smoke test 也没有加：这个 crate 目前没有自己的 `.github/workflows`
+//! （仓库里能看到的 workflow 全部来自 `depend/` 下的第三方库），加一条全新
+//! 的 CI 流水线超出了这次改动的范围。
+//!
+//! 这里只提供确实是纯 Rust、不碰 FFI 的那一小部分：[`keccak256`]。
+//! `Proof::verify`（见 [`crate::Proof`]）依赖 [`crate::Node::from_rlp`]，而
+//! 后者是通过 cxx 桥接调用 `node_from_rlp_alloc` 的，不是纯 Rust，所以这里
+//! 没有放一个"看起来能在 wasm32 下跑但其实是摆设"的影子实现；请求里提到的
+//! `CompactProof` 这个类型在这个裁剪后的代码树里也根本不存在，同样没有
+//! 东西可以包一层 `verify_key`。
+
+/// Keccak256，不依赖 cxx/FFI，可以在 wasm32 上直接使用
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    crate::keccak256(data)
+}